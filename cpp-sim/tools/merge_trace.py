@@ -91,6 +91,12 @@ def main() -> None:
         default=4096,
         help="Sybil max link walk (default: 4096)",
     )
+    parser.add_argument(
+        "--policy",
+        choices=["quarantine", "slash"],
+        default="quarantine",
+        help="Sybil equivocation policy (default: quarantine)",
+    )
     parser.add_argument(
         "--protocol-version",
         type=int,
@@ -117,6 +123,7 @@ def main() -> None:
             "quarantine_ticks": args.quarantine,
             "fixed_point_scale": args.fixed_point_scale,
             "max_link_walk": args.max_link_walk,
+            "policy": args.policy,
         },
         "final_merkle_root": final_root,
         "sources": [os.path.basename(p) for p in trace_paths],
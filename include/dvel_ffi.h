@@ -64,8 +64,15 @@ extern "C"
         DVEL_LINK_OK = 0,
         DVEL_LINK_ERR_DUPLICATE = 1,
         DVEL_LINK_ERR_MISSING_PARENT = 2,
+        DVEL_LINK_ERR_TOO_MANY_TIPS = 3,   // tip cap set via dvel_ledger_set_max_tips
+        DVEL_LINK_ERR_INVALID_GENESIS = 4, // second genesis under dvel_ledger_set_single_genesis
+        DVEL_LINK_ERR_ORPHANED = 5,        // parent removed by pruning
     } dvel_link_result_t;
 
+    // Link policies (both off by default). max_tips = 0 means unlimited.
+    void dvel_ledger_set_max_tips(dvel_ledger_t *ledger, size_t max_tips);
+    void dvel_ledger_set_single_genesis(dvel_ledger_t *ledger, bool enabled);
+
     // Linkage-aware add:
     // - checks duplicate
     // - checks parent existence unless genesis
@@ -93,6 +100,9 @@ extern "C"
         DVEL_ERR_INVALID_VERSION = 1,
         DVEL_ERR_INVALID_SIGNATURE = 2,
         DVEL_ERR_TIMESTAMP_NON_MONOTONIC = 3,
+        DVEL_ERR_TIMESTAMP_TOO_FAR_AHEAD = 4,
+        DVEL_ERR_PAYLOAD_TOO_LARGE = 5,
+        DVEL_ERR_PAYLOAD_HASH_MISMATCH = 6,
     } dvel_validation_result_t;
 
     // Minimal validation context.
@@ -115,6 +125,11 @@ extern "C"
     // DOES NOT check prev_hash existence (ledger linkage) - that is separate.
     dvel_validation_result_t dvel_validate_event(const dvel_event_t *event, dvel_validation_ctx_t *ctx);
 
+    // As dvel_validate_event, and also rejects timestamps more than max_forward_skew ahead
+    // of now (DVEL_ERR_TIMESTAMP_TOO_FAR_AHEAD). Pass UINT64_MAX to disable the bound.
+    dvel_validation_result_t dvel_validate_event_at(const dvel_event_t *event, dvel_validation_ctx_t *ctx,
+                                                    uint64_t now, uint64_t max_forward_skew);
+
     // ---------------- Scoring / Preference (no-consensus) ----------------
     typedef enum
     {
@@ -149,6 +164,12 @@ extern "C"
     typedef void dvel_sybil_overlay_t;
     typedef void dvel_trace_recorder_t;
 
+    typedef enum
+    {
+        DVEL_POLICY_QUARANTINE = 0,
+        DVEL_POLICY_SLASH = 1,
+    } dvel_equivocation_policy_t;
+
     typedef struct
     {
         uint64_t warmup_ticks;
@@ -182,9 +203,31 @@ extern "C"
     dvel_sybil_overlay_t *dvel_sybil_overlay_new(void);
     void dvel_sybil_overlay_free(dvel_sybil_overlay_t *ptr);
 
-    // Override overlay config in-place.
+    // Overlay built from cfg (NULL = defaults), quarantine policy. Free with dvel_sybil_free.
+    dvel_sybil_overlay_t *dvel_sybil_new(const dvel_sybil_config_t *cfg);
+    void dvel_sybil_free(dvel_sybil_overlay_t *overlay);
+
+    // Observe an event already linked into ledger, as seen by node_id at tick now.
+    // hash may be NULL (computed from ev). Returns false on NULL overlay/ledger/ev and
+    // sets dvel_last_error_message.
+    bool dvel_sybil_observe_event(
+        dvel_sybil_overlay_t *overlay,
+        const dvel_ledger_t *ledger,
+        uint64_t now,
+        uint32_t node_id,
+        const dvel_event_t *ev,
+        const dvel_hash_t *hash);
+
+    // Fixed-point author weight at tick now (0 for a NULL overlay).
+    uint64_t dvel_sybil_author_weight(const dvel_sybil_overlay_t *overlay, dvel_pubkey_t author, uint64_t now);
+
+    // Override overlay config in-place (the equivocation policy is kept).
     void dvel_sybil_overlay_set_config(dvel_sybil_overlay_t *overlay, const dvel_sybil_config_t *cfg);
 
+    // Set the equivocation policy (a dvel_equivocation_policy_t value). Returns false on a
+    // NULL overlay or unknown value, leaving the overlay unchanged.
+    bool dvel_sybil_set_policy(dvel_sybil_overlay_t *overlay, uint32_t policy);
+
     // Trace recorder (optional, proof tooling). Ownership belongs to caller.
     dvel_trace_recorder_t *dvel_trace_recorder_new(void);
     void dvel_trace_recorder_free(dvel_trace_recorder_t *ptr);
@@ -225,8 +268,14 @@ extern "C"
     // Writes the signature into out_sig.
     void dvel_sign_event(const dvel_event_t *event, const dvel_hash_t *secret_key, dvel_sig_t *out_sig);
 
+    // ---------------- Errors ----------------
+    // Thread-local message for the most recent failing storage, ledger-link or validation call.
+    // Copies it into buf as NUL-terminated UTF-8, truncated if len is too small (buf is still
+    // NUL-terminated when len > 0). Returns the size needed including the NUL; buf may be NULL.
+    size_t dvel_last_error_message(char *buf, size_t len);
+
     // ---------------- Storage (chunk/manifest/sign/verify) ----------------
-    // Copies last error string into buf (NUL-terminated if space). Returns full length of the message.
+    // Older name for dvel_last_error_message; returns the message length without the NUL.
     size_t dvel_storage_last_error(uint8_t *buf, size_t buf_len);
 
     // Chunk a file into out_dir and write manifest (.manifest). If sign=true, secret_key32 must be non-null.
@@ -237,15 +286,32 @@ extern "C"
         const uint8_t *secret_key32,
         bool sign);
 
-    // Verify manifest/chunks and reassemble to output_path. If expect_signer is non-null, it must match manifest signer.
+    // Verify manifest/chunks and reassemble to output_path. If expect_signer is non-null, it must be one of the manifest signers.
     bool dvel_storage_download(
         const char *manifest_path,
         const char *chunk_dir,
         const char *output_path,
         const uint8_t *expect_signer32);
 
+#ifdef _WIN32
+    // UTF-16 path variants of dvel_storage_chunk_file / dvel_storage_download (Windows only).
+    // On other platforms the char* variants accept any path bytes, UTF-8 or not.
+    bool dvel_storage_chunk_file_w(
+        const wchar_t *input_path,
+        const wchar_t *out_dir,
+        size_t chunk_size_bytes,
+        const uint8_t *secret_key32,
+        bool sign);
+
+    bool dvel_storage_download_w(
+        const wchar_t *manifest_path,
+        const wchar_t *chunk_dir,
+        const wchar_t *output_path,
+        const uint8_t *expect_signer32);
+#endif
+
     // Compute hashes from manifest (for anchoring into ledger events or audit).
-    // Returns false on error; use dvel_storage_last_error to inspect.
+    // Returns false on error; use dvel_last_error_message to inspect.
     bool dvel_storage_manifest_hash(const char *manifest_path, dvel_hash_t *out_hash);
     bool dvel_storage_chunk_merkle_root(const char *manifest_path, dvel_hash_t *out_hash);
 
@@ -0,0 +1,194 @@
+// Merkle root cost per appended event: full recompute, cached ledger tree, and an
+// append-only accumulator.
+//
+// `check_trace` queries `Ledger::merkle_root` after every row. Event hashes arrive in
+// effectively random order, while the root is defined over sorted leaves, so a new
+// leaf usually lands mid-list and shifts every later pairing. The ledger caches its
+// tree and rehashes only the nodes right of the insert, which is still O(N) per row
+// and roughly O(N^2) for a trace of N events.
+//
+// The accumulator below keeps one perfect-subtree root per set bit of the leaf count
+// (a binary-counter frontier): each push is amortised O(1) and each root O(log N), and
+// for leaves fed in sorted order it yields exactly the same root as `merkle_root`
+// (checked in `main` before measuring). It is only correct for append-only, sorted
+// leaves; it cannot absorb a leaf that sorts before existing ones, so it does not
+// apply to `check_trace` or any ledger fed in hash order. Its row shows what the
+// commitment would cost if it were defined over arrival order instead.
+//
+// Each case measures the per-row cost at ledger size N:
+//   recompute/N    sort N leaves (N-1 in hash-map order plus one new) and rebuild
+//                  the whole tree, i.e. the ledger without its cache
+//   ledger/N       link the Nth event into a warm (N-1)-event `Ledger` (cloned per
+//                  iteration, clone not timed) and query `merkle_root()`
+//   incremental/N  one sorted-order push onto an (N-1)-leaf accumulator plus `root()`
+//
+// Measured (x86-64 dev box, release, no SHA extensions):
+//   N          recompute     ledger        incremental (sorted appends only)
+//   100        13.9 us       2.2 us        0.8 us
+//   10,000     1.7 ms        400 us        1.7 us
+//   1,000,000  226 ms        51 ms         2.6 us
+// For random-order inserts the cache saves about 4-6x over a full recompute, but both
+// grow linearly per row; only the accumulator grows with log N, and only because its
+// leaves are appended in sorted order.
+//
+// Run: cargo bench --bench merkle_incremental
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group};
+use dvel_core::event::{Event, Hash, ZERO_HASH};
+use dvel_core::ledger::Ledger;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+const SIZES: [usize; 3] = [100, 10_000, 1_000_000];
+
+#[derive(Clone, Default)]
+struct IncrementalRoot {
+    // frontier[k] = root of the pending perfect subtree of 2^k leaves, if bit k of the
+    // leaf count is set.
+    frontier: Vec<Option<Hash>>,
+}
+
+fn pair(a: &Hash, b: &Hash) -> Hash {
+    let mut h = Sha256::new();
+    h.update(a);
+    h.update(b);
+    h.finalize().into()
+}
+
+impl IncrementalRoot {
+    fn push(&mut self, leaf: Hash) {
+        let mut carry = leaf;
+        for slot in self.frontier.iter_mut() {
+            match slot.take() {
+                Some(left) => carry = pair(&left, &carry),
+                None => {
+                    *slot = Some(carry);
+                    return;
+                }
+            }
+        }
+        self.frontier.push(Some(carry));
+    }
+
+    // Same shape as `Ledger::merkle_root`: an odd node at any level is paired with itself.
+    fn root(&self) -> Option<Hash> {
+        let top = self.frontier.iter().rposition(Option::is_some)?;
+        let mut carry: Option<Hash> = None;
+        for (k, slot) in self.frontier.iter().enumerate().take(top + 1) {
+            carry = match (slot, carry) {
+                (Some(node), Some(c)) => Some(pair(node, &c)),
+                (Some(node), None) if k == top => Some(*node),
+                (Some(node), None) => Some(pair(node, node)),
+                (None, Some(c)) => Some(pair(&c, &c)),
+                (None, None) => None,
+            };
+        }
+        carry
+    }
+}
+
+fn genesis(i: u64) -> Event {
+    let mut payload_hash = [0u8; 32];
+    payload_hash[..8].copy_from_slice(&i.to_le_bytes());
+    Event {
+        version: 1,
+        prev_hash: ZERO_HASH,
+        author: [7u8; 32],
+        timestamp: i,
+        payload_hash,
+        signature: [0u8; 64],
+    }
+}
+
+fn make_ledger(n: usize) -> Ledger {
+    let mut ledger = Ledger::new();
+    for i in 0..n as u64 {
+        ledger
+            .try_add_event(genesis(i))
+            .expect("distinct genesis events link");
+    }
+    ledger
+}
+
+fn sorted_leaves(ledger: &Ledger) -> Vec<Hash> {
+    let mut leaves: Vec<Hash> = ledger.hashes_set().into_iter().collect();
+    leaves.sort_unstable();
+    leaves
+}
+
+// From-scratch root, as `merkle_root` computed it before the ledger cached its tree.
+fn recompute(leaves: &mut [Hash]) -> Option<Hash> {
+    leaves.sort_unstable();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|p| pair(&p[0], p.get(1).unwrap_or(&p[0])))
+            .collect();
+    }
+    level.first().copied()
+}
+
+fn check_equivalence() {
+    for n in 1..=70 {
+        let ledger = make_ledger(n);
+        let mut acc = IncrementalRoot::default();
+        sorted_leaves(&ledger).into_iter().for_each(|h| acc.push(h));
+        assert_eq!(acc.root(), ledger.merkle_root(), "root mismatch at n={}", n);
+        let mut leaves: Vec<Hash> = ledger.hashes_set().into_iter().collect();
+        assert_eq!(recompute(&mut leaves), ledger.merkle_root(), "n={}", n);
+    }
+}
+
+fn bench_merkle(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merkle_root_per_row");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(5));
+
+    for &n in &SIZES {
+        // Warm cache over N-1 events; each iteration links the Nth and queries the root.
+        let ledger = make_ledger(n - 1);
+        ledger.merkle_root();
+        let fresh = genesis(n as u64);
+        let mut unsorted: Vec<Hash> = ledger.hashes_set().into_iter().collect();
+        unsorted.push(Ledger::hash_event(&fresh));
+
+        let leaves = sorted_leaves(&make_ledger(n));
+        let (last, head) = leaves.split_last().unwrap();
+        let mut acc = IncrementalRoot::default();
+        head.iter().for_each(|h| acc.push(*h));
+
+        group.bench_with_input(BenchmarkId::new("recompute", n), &unsorted, |b, u| {
+            b.iter_batched_ref(|| u.clone(), |u| recompute(u), BatchSize::LargeInput)
+        });
+        group.bench_with_input(BenchmarkId::new("ledger", n), &ledger, |b, l| {
+            b.iter_batched_ref(
+                || l.clone(),
+                |l| {
+                    l.try_add_event(fresh.clone()).unwrap();
+                    l.merkle_root()
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("incremental", n), &acc, |b, acc| {
+            b.iter_batched(
+                || acc.clone(),
+                |mut a| {
+                    a.push(*last);
+                    a.root()
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_merkle);
+
+fn main() {
+    check_equivalence();
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+}
@@ -3,6 +3,9 @@
 pub mod config;
 pub mod http;
 pub mod node;
+pub mod quorum;
 pub mod slashing;
 pub mod storage;
 pub mod types;
+
+pub use quorum::{verify_quorum, BftError};
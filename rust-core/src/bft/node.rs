@@ -351,6 +351,7 @@ impl Node {
             tx_root,
             proposer_id: self.node_id,
             timestamp_ms: now_ms(),
+            quorum_cert: None,
         };
 
         Block { header, txs }
@@ -0,0 +1,228 @@
+use crate::bft::types::{BlockHeader, header_bytes};
+use crate::event::PublicKey;
+use ed25519_dalek::{PublicKey as DalekPublicKey, Signature, Verifier};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Reasons a block header's quorum certificate is rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BftError {
+    MissingQuorumCert,
+    /// `signers` and `signatures` differ in length.
+    LengthMismatch {
+        signers: usize,
+        signatures: usize,
+    },
+    UnknownSigner {
+        index: usize,
+    },
+    DuplicateSigner {
+        index: usize,
+    },
+    InvalidSignature {
+        index: usize,
+    },
+    BelowThreshold {
+        signers: usize,
+        threshold: usize,
+    },
+}
+
+impl fmt::Display for BftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BftError::MissingQuorumCert => write!(f, "block header has no quorum certificate"),
+            BftError::LengthMismatch {
+                signers,
+                signatures,
+            } => write!(
+                f,
+                "quorum certificate has {} signers but {} signatures",
+                signers, signatures
+            ),
+            BftError::UnknownSigner { index } => {
+                write!(f, "signer {} is not in the validator set", index)
+            }
+            BftError::DuplicateSigner { index } => {
+                write!(f, "signer {} appears more than once", index)
+            }
+            BftError::InvalidSignature { index } => {
+                write!(f, "signature {} does not verify over the header", index)
+            }
+            BftError::BelowThreshold { signers, threshold } => write!(
+                f,
+                "quorum certificate has {} signers, threshold is {}",
+                signers, threshold
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BftError {}
+
+/// Verify the quorum certificate attached to `header`.
+///
+/// Every signer must belong to `validator_set`, appear at most once and have
+/// signed `header_bytes(header)`. At least `threshold` such signers are
+/// required. Structural checks run first so a malformed certificate is
+/// rejected before any signature work; signature checks run on rayon under
+/// the `parallel` feature.
+pub fn verify_quorum(
+    header: &BlockHeader,
+    validator_set: &[PublicKey],
+    threshold: usize,
+) -> Result<(), BftError> {
+    let qc = header
+        .quorum_cert
+        .as_ref()
+        .ok_or(BftError::MissingQuorumCert)?;
+    if qc.signers.len() != qc.signatures.len() {
+        return Err(BftError::LengthMismatch {
+            signers: qc.signers.len(),
+            signatures: qc.signatures.len(),
+        });
+    }
+
+    let validators: HashSet<&PublicKey> = validator_set.iter().collect();
+    let mut seen: HashSet<&PublicKey> = HashSet::with_capacity(qc.signers.len());
+    for (index, signer) in qc.signers.iter().enumerate() {
+        if !validators.contains(signer) {
+            return Err(BftError::UnknownSigner { index });
+        }
+        if !seen.insert(signer) {
+            return Err(BftError::DuplicateSigner { index });
+        }
+    }
+    if qc.signers.len() < threshold {
+        return Err(BftError::BelowThreshold {
+            signers: qc.signers.len(),
+            threshold,
+        });
+    }
+
+    let msg = header_bytes(header);
+    let check = |index: usize| -> Result<(), BftError> {
+        let ok = DalekPublicKey::from_bytes(&qc.signers[index])
+            .ok()
+            .zip(Signature::from_bytes(&qc.signatures[index]).ok())
+            .is_some_and(|(pk, sig)| pk.verify(&msg, &sig).is_ok());
+        if ok {
+            Ok(())
+        } else {
+            Err(BftError::InvalidSignature { index })
+        }
+    };
+
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        // find_first keeps the reported index deterministic across runs.
+        match (0..qc.signers.len())
+            .into_par_iter()
+            .map(check)
+            .find_first(|r| r.is_err())
+        {
+            Some(err) => err,
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        (0..qc.signers.len()).try_for_each(check)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bft::types::QuorumCert;
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = DalekPublicKey::from(&secret);
+        Keypair { secret, public }
+    }
+
+    fn header() -> BlockHeader {
+        BlockHeader {
+            height: 7,
+            round: 1,
+            prev_block_hash: [1u8; 32],
+            tx_root: [2u8; 32],
+            proposer_id: [3u8; 32],
+            timestamp_ms: 1_000,
+            quorum_cert: None,
+        }
+    }
+
+    fn certify(header: &mut BlockHeader, signers: &[&Keypair]) {
+        let msg = header_bytes(header);
+        header.quorum_cert = Some(QuorumCert {
+            signers: signers.iter().map(|k| k.public.to_bytes()).collect(),
+            signatures: signers.iter().map(|k| k.sign(&msg).to_bytes()).collect(),
+        });
+    }
+
+    fn validators() -> (Vec<Keypair>, Vec<PublicKey>) {
+        let keys: Vec<Keypair> = (1..=4).map(keypair).collect();
+        let set = keys.iter().map(|k| k.public.to_bytes()).collect();
+        (keys, set)
+    }
+
+    #[test]
+    fn exactly_threshold_is_accepted() {
+        let (keys, set) = validators();
+        let mut h = header();
+        certify(&mut h, &[&keys[0], &keys[2], &keys[3]]);
+        assert_eq!(verify_quorum(&h, &set, 3), Ok(()));
+    }
+
+    #[test]
+    fn below_threshold_is_rejected() {
+        let (keys, set) = validators();
+        let mut h = header();
+        certify(&mut h, &[&keys[0], &keys[1]]);
+        assert_eq!(
+            verify_quorum(&h, &set, 3),
+            Err(BftError::BelowThreshold {
+                signers: 2,
+                threshold: 3
+            })
+        );
+    }
+
+    #[test]
+    fn duplicate_signer_is_rejected() {
+        let (keys, set) = validators();
+        let mut h = header();
+        certify(&mut h, &[&keys[0], &keys[1], &keys[1]]);
+        assert_eq!(
+            verify_quorum(&h, &set, 3),
+            Err(BftError::DuplicateSigner { index: 2 })
+        );
+    }
+
+    #[test]
+    fn unknown_signer_and_bad_signature_are_rejected() {
+        let (keys, set) = validators();
+        let outsider = keypair(9);
+        let mut h = header();
+        certify(&mut h, &[&keys[0], &outsider]);
+        assert_eq!(
+            verify_quorum(&h, &set, 2),
+            Err(BftError::UnknownSigner { index: 1 })
+        );
+
+        certify(&mut h, &[&keys[0], &keys[1]]);
+        h.timestamp_ms += 1;
+        assert_eq!(
+            verify_quorum(&h, &set, 2),
+            Err(BftError::InvalidSignature { index: 0 })
+        );
+
+        h.quorum_cert = None;
+        assert_eq!(verify_quorum(&h, &set, 0), Err(BftError::MissingQuorumCert));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::event::{Hash, PublicKey, ZERO_HASH};
+use crate::event::{Hash, PublicKey, Signature, ZERO_HASH};
 use sha2::{Digest, Sha256};
 
 #[cfg(feature = "bft")]
@@ -56,6 +56,43 @@ pub struct BlockHeader {
     pub tx_root: Hash,
     pub proposer_id: NodeId,
     pub timestamp_ms: u64,
+    /// Commit signatures over `header_bytes`; not part of the block hash.
+    #[cfg_attr(feature = "bft", serde(default))]
+    pub quorum_cert: Option<QuorumCert>,
+}
+
+/// Validator signatures attesting to a block header.
+///
+/// `signatures[i]` is the signature produced by `signers[i]` over
+/// `header_bytes(header)`. Checked by `bft::verify_quorum`.
+#[cfg_attr(feature = "bft", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default)]
+pub struct QuorumCert {
+    pub signers: Vec<PublicKey>,
+    #[cfg_attr(feature = "bft", serde(with = "sig_vec_hex"))]
+    pub signatures: Vec<Signature>,
+}
+
+// Signatures travel as hex strings, like `SignedVote::signature`.
+#[cfg(feature = "bft")]
+mod sig_vec_hex {
+    use crate::event::Signature;
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(sigs: &[Signature], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(sigs.iter().map(hex::encode))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Signature>, D::Error> {
+        let raw: Vec<String> = Vec::deserialize(d)?;
+        raw.iter()
+            .map(|h| {
+                let bytes = hex::decode(h).map_err(D::Error::custom)?;
+                <Signature>::try_from(bytes.as_slice())
+                    .map_err(|_| D::Error::custom("signature must be 64 bytes"))
+            })
+            .collect()
+    }
 }
 
 #[cfg_attr(feature = "bft", derive(Serialize, Deserialize))]
@@ -1,9 +1,16 @@
 use dvel_core::storage::{
-    chunk_file_to_dir, manifest_path, read_manifest, reassemble, sign_manifest_inplace,
-    verify_chunks, verify_manifest_signature, write_manifest,
+    IoOptions, Manifest, chunk_file_to_dir_with_options, manifest_path, read_manifest, reassemble,
+    run_selfcheck, sign_manifest_inplace, verify_chunks_with_options, verify_manifest_signature,
+    write_manifest, write_manifest_reproducible,
 };
+use ed25519_dalek::{PublicKey as DalekPublicKey, SecretKey};
 use std::env;
-use std::path::PathBuf;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Environment variable holding the signing secret as hex.
+const SIGN_KEY_ENV: &str = "DVEL_SIGN_KEY";
 
 fn parse_hex_array<const N: usize>(s: &str) -> Result<[u8; N], String> {
     let bytes = hex::decode(s).map_err(|_| "bad hex")?;
@@ -15,14 +22,90 @@ fn parse_hex_array<const N: usize>(s: &str) -> Result<[u8; N], String> {
     Ok(out)
 }
 
+/// Key file: 32 raw bytes, or 64 hex chars with optional trailing newline.
+fn load_secret_file(path: &Path) -> Result<[u8; 32], String> {
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    if bytes.len() == 32 {
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&bytes);
+        return Ok(out);
+    }
+    let text = std::str::from_utf8(&bytes)
+        .map_err(|_| format!("{}: expected 32 raw bytes or hex", path.display()))?;
+    parse_hex_array::<32>(text.trim_end())
+}
+
+// Upper bounds for the tuning flags; anything larger is almost certainly a typo.
+const MAX_JOBS: usize = 1024;
+const MAX_READAHEAD: usize = 65_536;
+const MAX_IO_BUFFER: usize = 256 * 1024 * 1024;
+/// Cap on readahead * chunk_size, the parallel pipeline's buffered bytes.
+const MAX_READAHEAD_BYTES: u64 = 16 * 1024 * 1024 * 1024;
+
 fn usage() {
     eprintln!("Usage:");
     eprintln!(
-        "  dvel-file upload <input_file> <out_dir> <chunk_size_bytes> [--sign <secret_hex32>]"
+        "  dvel-file upload <input_file> <out_dir> <chunk_size_bytes> [--sign-file <path> | --sign <secret_hex32>] [--chunk-suffix <sfx>] [--reproducible] [--json] [tuning]"
+    );
+    eprintln!(
+        "    signing key may also come from ${} (hex); --sign is insecure (shell history, ps)",
+        SIGN_KEY_ENV
+    );
+    eprintln!(
+        "  dvel-file download <manifest_path> <chunk_dir> <output_path> [--expect-signer <pubkey_hex32>] [tuning]"
+    );
+    eprintln!(
+        "  dvel-file verify <manifest_path> <chunk_dir> [--expect-signer <pubkey_hex32>] [tuning]"
+    );
+    eprintln!("  dvel-file keygen [--out <prefix>] [--force]   (needs --features keygen)");
+    eprintln!(
+        "    writes <prefix>.secret (0600) and <prefix>.public, or prints both; the public key is what --expect-signer takes"
     );
+    eprintln!("  dvel-file selfcheck");
     eprintln!(
-        "  dvel-file download <manifest_path> <chunk_dir> <output_path> [--expect-signer <pubkey_hex32>]"
+        "  tuning: --jobs <1-{}> (default: cores) --readahead <1-{} chunks> (default 64) --io-buffer <1-{} bytes> (default 65536)",
+        MAX_JOBS, MAX_READAHEAD, MAX_IO_BUFFER
     );
+    eprintln!("    --jobs and --readahead take effect in builds with the `parallel` feature");
+}
+
+/// Applies a `--jobs` / `--readahead` / `--io-buffer` flag to `opts`.
+/// Returns Ok(false) if `flag` is not a tuning flag.
+fn parse_io_option(
+    opts: &mut IoOptions,
+    flag: &str,
+    value: Option<&String>,
+) -> Result<bool, String> {
+    let (slot, max) = match flag {
+        "--jobs" => (&mut opts.jobs, MAX_JOBS),
+        "--readahead" => (&mut opts.readahead, MAX_READAHEAD),
+        "--io-buffer" => (&mut opts.io_buffer, MAX_IO_BUFFER),
+        _ => return Ok(false),
+    };
+    let value = value.ok_or_else(|| format!("missing value for {}", flag))?;
+    *slot = value
+        .parse::<usize>()
+        .ok()
+        .filter(|n| (1..=max).contains(n))
+        .ok_or_else(|| format!("{} must be an integer in 1..={}", flag, max))?;
+    Ok(true)
+}
+
+/// Bounds `--readahead` by the largest chunk it would buffer (`MAX_READAHEAD_BYTES`).
+fn check_readahead_bytes(opts: &IoOptions, chunk_len: u64) -> Result<(), String> {
+    if (opts.readahead as u64).saturating_mul(chunk_len) > MAX_READAHEAD_BYTES {
+        return Err(format!(
+            "--readahead * chunk_size exceeds {} bytes",
+            MAX_READAHEAD_BYTES
+        ));
+    }
+    Ok(())
+}
+
+/// Largest chunk the manifest declares, for `check_readahead_bytes`.
+fn largest_chunk(manifest: &Manifest) -> u64 {
+    manifest.chunks.iter().map(|c| c.len).max().unwrap_or(0)
 }
 
 fn handle_upload(args: &[String]) -> Result<(), String> {
@@ -35,7 +118,11 @@ fn handle_upload(args: &[String]) -> Result<(), String> {
         .parse()
         .map_err(|_| "chunk_size must be an integer")?;
 
-    let mut sign_key: Option<[u8; 32]> = None;
+    let mut sign_keys: Vec<(&str, [u8; 32])> = Vec::new();
+    let mut chunk_suffix: Option<&str> = None;
+    let mut reproducible = false;
+    let mut json = false;
+    let mut io_opts = IoOptions::default();
     let mut idx = 3;
     while idx < args.len() {
         match args[idx].as_str() {
@@ -43,21 +130,74 @@ fn handle_upload(args: &[String]) -> Result<(), String> {
                 if idx + 1 >= args.len() {
                     return Err("missing value for --sign".into());
                 }
-                sign_key = Some(parse_hex_array::<32>(&args[idx + 1])?);
+                eprintln!(
+                    "warning: --sign exposes the secret key in shell history and the process table; use --sign-file or ${}",
+                    SIGN_KEY_ENV
+                );
+                sign_keys.push(("--sign", parse_hex_array::<32>(&args[idx + 1])?));
+                idx += 2;
+            }
+            "--sign-file" => {
+                if idx + 1 >= args.len() {
+                    return Err("missing value for --sign-file".into());
+                }
+                sign_keys.push(("--sign-file", load_secret_file(Path::new(&args[idx + 1]))?));
                 idx += 2;
             }
+            "--chunk-suffix" => {
+                if idx + 1 >= args.len() {
+                    return Err("missing value for --chunk-suffix".into());
+                }
+                chunk_suffix = Some(&args[idx + 1]);
+                idx += 2;
+            }
+            "--reproducible" => {
+                reproducible = true;
+                idx += 1;
+            }
+            "--json" => {
+                if !cfg!(feature = "json") {
+                    return Err("--json requires building with --features json".into());
+                }
+                json = true;
+                idx += 1;
+            }
+            flag if parse_io_option(&mut io_opts, flag, args.get(idx + 1))? => idx += 2,
             other => return Err(format!("unknown arg {}", other)),
         }
     }
+    if let Ok(v) = env::var(SIGN_KEY_ENV) {
+        let key =
+            parse_hex_array::<32>(v.trim()).map_err(|e| format!("{}: {}", SIGN_KEY_ENV, e))?;
+        sign_keys.push((SIGN_KEY_ENV, key));
+    }
+    if sign_keys.len() > 1 {
+        let sources: Vec<&str> = sign_keys.iter().map(|(src, _)| *src).collect();
+        return Err(format!(
+            "multiple signing key sources given ({}); supply exactly one",
+            sources.join(", ")
+        ));
+    }
+    let sign_key = sign_keys.pop().map(|(_, k)| k);
+    check_readahead_bytes(&io_opts, chunk_size as u64)?;
 
     let mut manifest =
-        chunk_file_to_dir(&input, &out_dir, chunk_size).map_err(|e| format!("{}", e))?;
+        chunk_file_to_dir_with_options(&input, &out_dir, chunk_size, chunk_suffix, &io_opts)
+            .map_err(|e| format!("{}", e))?;
     if let Some(sk) = sign_key {
         sign_manifest_inplace(&mut manifest, &sk).map_err(|e| format!("{}", e))?;
     }
 
     let mpath = manifest_path(&out_dir, &manifest.file_name);
-    write_manifest(&manifest, &mpath).map_err(|e| format!("{}", e))?;
+    if reproducible {
+        write_manifest_reproducible(&manifest, &mpath)
+    } else {
+        write_manifest(&manifest, &mpath)
+    }
+    .map_err(|e| format!("{}", e))?;
+    if json {
+        write_manifest_json(&manifest, &mpath)?;
+    }
 
     println!(
         "Chunked {} into {} chunks -> {}",
@@ -68,6 +208,19 @@ fn handle_upload(args: &[String]) -> Result<(), String> {
     Ok(())
 }
 
+/// `<name>.manifest.json` next to the text manifest (`Manifest::to_json`).
+#[cfg(feature = "json")]
+fn write_manifest_json(manifest: &Manifest, mpath: &Path) -> Result<(), String> {
+    let mut path = mpath.as_os_str().to_owned();
+    path.push(".json");
+    std::fs::write(&path, manifest.to_json()).map_err(|e| format!("write {:?}: {}", path, e))
+}
+
+#[cfg(not(feature = "json"))]
+fn write_manifest_json(_manifest: &Manifest, _mpath: &Path) -> Result<(), String> {
+    unreachable!("--json is rejected without the json feature")
+}
+
 fn handle_download(args: &[String]) -> Result<(), String> {
     if args.len() < 3 {
         return Err("download requires <manifest_path> <chunk_dir> <output_path>".into());
@@ -76,8 +229,54 @@ fn handle_download(args: &[String]) -> Result<(), String> {
     let chunk_dir = PathBuf::from(&args[1]);
     let output_path = PathBuf::from(&args[2]);
 
+    let (expect_signer, io_opts) = parse_check_options(&args[3..])?;
+    let manifest = read_manifest(&manifest_path).map_err(|e| format!("{}", e))?;
+    check_readahead_bytes(&io_opts, largest_chunk(&manifest))?;
+    check_signature(&manifest, expect_signer)?;
+
+    verify_chunks_with_options(&manifest, &chunk_dir, &io_opts).map_err(|e| format!("{}", e))?;
+    reassemble(&manifest, &chunk_dir, &output_path).map_err(|e| format!("{}", e))?;
+    println!("Reassembled -> {}", output_path.display());
+    Ok(())
+}
+
+/// Integrity check without reassembly: signature (if any), chunks, and a summary line.
+fn handle_verify(args: &[String]) -> Result<(), String> {
+    if args.len() < 2 {
+        return Err("verify requires <manifest_path> <chunk_dir>".into());
+    }
+    let manifest_path = PathBuf::from(&args[0]);
+    let chunk_dir = PathBuf::from(&args[1]);
+
+    let (expect_signer, io_opts) = parse_check_options(&args[2..])?;
+    let manifest = read_manifest(&manifest_path).map_err(|e| format!("{}", e))?;
+    check_readahead_bytes(&io_opts, largest_chunk(&manifest))?;
+    check_signature(&manifest, expect_signer)?;
+    verify_chunks_with_options(&manifest, &chunk_dir, &io_opts).map_err(|e| format!("{}", e))?;
+
+    println!("manifest_hash={}", hex::encode(manifest.hash()));
+    match manifest.chunk_merkle_root() {
+        Some(root) => println!("chunk_merkle_root={}", hex::encode(root)),
+        None => println!("chunk_merkle_root=none"),
+    }
+    if let Some(root) = manifest.chunk_merkle_root_v2() {
+        println!("chunk_merkle_root_v2={}", hex::encode(root));
+    }
+    println!(
+        "OK {}: {} chunks, {} bytes, {} signature(s)",
+        manifest.file_name,
+        manifest.chunks.len(),
+        manifest.total_size,
+        manifest.signers.len()
+    );
+    Ok(())
+}
+
+/// Parses the trailing `[--expect-signer <pubkey_hex32>]` and tuning options.
+fn parse_check_options(args: &[String]) -> Result<(Option<[u8; 32]>, IoOptions), String> {
     let mut expect_signer: Option<[u8; 32]> = None;
-    let mut idx = 3;
+    let mut io_opts = IoOptions::default();
+    let mut idx = 0;
     while idx < args.len() {
         match args[idx].as_str() {
             "--expect-signer" => {
@@ -87,22 +286,130 @@ fn handle_download(args: &[String]) -> Result<(), String> {
                 expect_signer = Some(parse_hex_array::<32>(&args[idx + 1])?);
                 idx += 2;
             }
+            flag if parse_io_option(&mut io_opts, flag, args.get(idx + 1))? => idx += 2,
             other => return Err(format!("unknown arg {}", other)),
         }
     }
+    Ok((expect_signer, io_opts))
+}
 
-    let manifest = read_manifest(&manifest_path).map_err(|e| format!("{}", e))?;
-    if let Some(exp) = expect_signer && manifest.signer != Some(exp) {
+/// Expected signer must be present; any signatures present must verify.
+fn check_signature(manifest: &Manifest, expect_signer: Option<[u8; 32]>) -> Result<(), String> {
+    if let Some(exp) = expect_signer && !manifest.has_signer(&exp) {
         return Err("manifest signer does not match expected key".into());
     }
+    if !manifest.signers.is_empty() {
+        verify_manifest_signature(manifest).map_err(|e| format!("{}", e))?;
+    }
+    Ok(())
+}
+
+/// Fresh ed25519 keypair from the OS CSPRNG. The public key is derived exactly as
+/// `sign_manifest_inplace` derives the signer, so it matches `--expect-signer`.
+fn handle_keygen(args: &[String]) -> Result<(), String> {
+    if !cfg!(feature = "keygen") {
+        return Err("keygen requires building with --features keygen".into());
+    }
+    let mut prefix: Option<&str> = None;
+    let mut force = false;
+    let mut idx = 0;
+    while idx < args.len() {
+        match args[idx].as_str() {
+            "--out" => {
+                if idx + 1 >= args.len() {
+                    return Err("missing value for --out".into());
+                }
+                prefix = Some(&args[idx + 1]);
+                idx += 2;
+            }
+            "--force" => {
+                force = true;
+                idx += 1;
+            }
+            other => return Err(format!("unknown arg {}", other)),
+        }
+    }
+
+    let secret = os_random_secret()?;
+    let sk = SecretKey::from_bytes(&secret).map_err(|e| format!("{}", e))?;
+    let public = DalekPublicKey::from(&sk).to_bytes();
 
-    if manifest.signature.is_some() {
-        verify_manifest_signature(&manifest).map_err(|e| format!("{}", e))?;
+    let Some(prefix) = prefix else {
+        println!("secret={}", hex::encode(secret));
+        println!("public={}", hex::encode(public));
+        return Ok(());
+    };
+    let secret_path = PathBuf::from(format!("{}.secret", prefix));
+    let public_path = PathBuf::from(format!("{}.public", prefix));
+    // Open both before writing either, so a refusal never leaves a half-written pair.
+    let secret_file = create_key_file(&secret_path, true, force)?;
+    let public_file = match create_key_file(&public_path, false, force) {
+        Ok(f) => f,
+        Err(e) => {
+            drop(secret_file);
+            let _ = std::fs::remove_file(&secret_path);
+            return Err(e);
+        }
+    };
+    write_key_file(secret_file, &secret_path, &hex::encode(secret))?;
+    write_key_file(public_file, &public_path, &hex::encode(public))?;
+    println!("secret -> {}", secret_path.display());
+    println!("public={}", hex::encode(public));
+    Ok(())
+}
+
+#[cfg(feature = "keygen")]
+fn os_random_secret() -> Result<[u8; 32], String> {
+    let mut secret = [0u8; 32];
+    getrandom::getrandom(&mut secret).map_err(|e| format!("cannot read OS randomness: {}", e))?;
+    Ok(secret)
+}
+
+#[cfg(not(feature = "keygen"))]
+fn os_random_secret() -> Result<[u8; 32], String> {
+    unreachable!("keygen is rejected without the keygen feature")
+}
+
+/// Creates `path` exclusively (`create_new`, so an existing file or symlink is never
+/// followed); `private` files are created 0600 on Unix. With `force` an existing entry is
+/// unlinked first, which replaces a symlink rather than writing through it.
+fn create_key_file(path: &Path, private: bool, force: bool) -> Result<File, String> {
+    if force {
+        match std::fs::remove_file(path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("cannot replace {}: {}", path.display(), e)),
+        }
+    }
+    let mut opts = OpenOptions::new();
+    opts.write(true).create_new(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(0o600);
     }
+    #[cfg(not(unix))]
+    let _ = private;
+    opts.open(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            format!("{} exists; pass --force to overwrite", path.display())
+        } else {
+            format!("cannot write {}: {}", path.display(), e)
+        }
+    })
+}
 
-    verify_chunks(&manifest, &chunk_dir).map_err(|e| format!("{}", e))?;
-    reassemble(&manifest, &chunk_dir, &output_path).map_err(|e| format!("{}", e))?;
-    println!("Reassembled -> {}", output_path.display());
+/// Writes `hex` plus a newline.
+fn write_key_file(mut f: File, path: &Path, hex: &str) -> Result<(), String> {
+    writeln!(f, "{}", hex).map_err(|e| format!("cannot write {}: {}", path.display(), e))
+}
+
+fn handle_selfcheck(args: &[String]) -> Result<(), String> {
+    if let Some(extra) = args.first() {
+        return Err(format!("unknown arg {}", extra));
+    }
+    run_selfcheck().map_err(|invariant| format!("FAIL {}", invariant))?;
+    println!("OK");
     Ok(())
 }
 
@@ -118,6 +425,9 @@ fn main() {
     let result: Result<(), String> = match cmd.as_str() {
         "upload" => handle_upload(&args),
         "download" => handle_download(&args),
+        "verify" => handle_verify(&args),
+        "keygen" => handle_keygen(&args),
+        "selfcheck" => handle_selfcheck(&args),
         _ => {
             usage();
             Err("unknown command".into())
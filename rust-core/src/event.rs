@@ -1,5 +1,7 @@
 // Event definition: fixed layout, deterministic canonical form; identity = SHA256(canonical || sig)
 use crate::PROTOCOL_VERSION;
+use ed25519_dalek::{PublicKey as DalekPublicKey, Signature as DalekSignature, Verifier};
+use sha2::{Digest, Sha256};
 
 pub type Hash = [u8; 32];
 pub type PublicKey = [u8; 32];
@@ -8,6 +10,39 @@ pub type Signature = [u8; 64];
 /// Genesis marker (no parent).
 pub const ZERO_HASH: Hash = [0u8; 32];
 
+/// Domain-separation prefix on the signing input from protocol version 2 onward, so an
+/// event signature can never be replayed as (or from) a signature in another context.
+pub const EVENT_SIGNING_DOMAIN: &[u8] = b"dvel-event-v1\n";
+
+/// Size of `canonical_bytes`: version, prev_hash, author, timestamp, payload_hash.
+pub const EVENT_CANONICAL_LEN: usize = 1 + 32 + 32 + 8 + 32;
+
+/// Size of the fixed wire encoding: canonical bytes followed by the signature.
+pub const WIRE_LEN: usize = EVENT_CANONICAL_LEN + 64;
+
+#[derive(Debug, PartialEq)]
+pub enum EventError {
+    /// Input shorter than `WIRE_LEN`.
+    TruncatedInput { len: usize },
+    /// Input longer than `WIRE_LEN`.
+    TrailingBytes { len: usize },
+}
+
+impl std::fmt::Display for EventError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventError::TruncatedInput { len } => {
+                write!(f, "event truncated: {} of {} bytes", len, WIRE_LEN)
+            }
+            EventError::TrailingBytes { len } => {
+                write!(f, "event has trailing bytes: {} of {} bytes", len, WIRE_LEN)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EventError {}
+
 #[derive(Debug, Clone)]
 pub struct Event {
     pub version: u8,
@@ -59,19 +94,214 @@ impl Event {
     /// Canonical byte encoding (hash/sign input).
     /// Layout: [version (1)] [prev_hash (32)] [author (32)] [timestamp (8 LE)] [payload_hash (32)].
     pub fn canonical_bytes(&self) -> Vec<u8> {
-        let mut out = Vec::with_capacity(1 + 32 + 32 + 8 + 32);
-        out.push(self.version);
-        out.extend_from_slice(&self.prev_hash);
-        out.extend_from_slice(&self.author);
-        out.extend_from_slice(&self.timestamp.to_le_bytes());
-        out.extend_from_slice(&self.payload_hash);
+        self.canonical_array().to_vec()
+    }
+
+    /// Allocation-free `canonical_bytes` for hot hashing/signing paths.
+    pub fn canonical_array(&self) -> [u8; EVENT_CANONICAL_LEN] {
+        let mut out = [0u8; EVENT_CANONICAL_LEN];
+        out[0] = self.version;
+        out[1..33].copy_from_slice(&self.prev_hash);
+        out[33..65].copy_from_slice(&self.author);
+        out[65..73].copy_from_slice(&self.timestamp.to_le_bytes());
+        out[73..105].copy_from_slice(&self.payload_hash);
         out
     }
 
+    /// Bytes covered by `signature`: canonical bytes, prefixed with `EVENT_SIGNING_DOMAIN`
+    /// for version >= 2. Version 1 events keep the legacy unprefixed input.
+    pub fn signing_input(&self) -> Vec<u8> {
+        let canonical = self.canonical_array();
+        if self.version < 2 {
+            return canonical.to_vec();
+        }
+        let mut out = Vec::with_capacity(EVENT_SIGNING_DOMAIN.len() + canonical.len());
+        out.extend_from_slice(EVENT_SIGNING_DOMAIN);
+        out.extend_from_slice(&canonical);
+        out
+    }
+
+    /// Check `signature` against `author` over `signing_input`.
+    pub fn verify_signature(&self) -> bool {
+        let Ok(pk) = DalekPublicKey::from_bytes(&self.author) else {
+            return false;
+        };
+        let Ok(sig) = DalekSignature::from_bytes(&self.signature) else {
+            return false;
+        };
+        pk.verify(&self.signing_input(), &sig).is_ok()
+    }
+
     /// Identity material: canonical bytes concatenated with signature.
     pub fn hash_material(&self) -> Vec<u8> {
         let mut out = self.canonical_bytes();
         out.extend_from_slice(&self.signature);
         out
     }
+
+    /// Fixed-layout wire encoding: canonical bytes followed by the 64-byte signature.
+    pub fn to_wire(&self) -> [u8; WIRE_LEN] {
+        let mut out = [0u8; WIRE_LEN];
+        out[..EVENT_CANONICAL_LEN].copy_from_slice(&self.canonical_array());
+        out[EVENT_CANONICAL_LEN..].copy_from_slice(&self.signature);
+        out
+    }
+
+    /// Decode `to_wire` output. Only the length is checked; version and signature are not.
+    pub fn from_wire(bytes: &[u8]) -> Result<Event, EventError> {
+        if bytes.len() < WIRE_LEN {
+            return Err(EventError::TruncatedInput { len: bytes.len() });
+        }
+        if bytes.len() > WIRE_LEN {
+            return Err(EventError::TrailingBytes { len: bytes.len() });
+        }
+        let mut ts = [0u8; 8];
+        ts.copy_from_slice(&bytes[65..73]);
+        Ok(Event::from_raw(
+            bytes[0],
+            bytes[1..33].try_into().unwrap(),
+            bytes[33..65].try_into().unwrap(),
+            u64::from_le_bytes(ts),
+            bytes[73..105].try_into().unwrap(),
+            bytes[105..].try_into().unwrap(),
+        ))
+    }
+}
+
+/// Payload facts tracked beside an event. The wire layout has no room for a length, so
+/// the size commitment travels separately and is checked against the event's
+/// `payload_hash` by `validation::validate_payload`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadInfo {
+    pub len: u64,
+    /// SHA256 of the payload bytes.
+    pub hash: Hash,
+}
+
+impl PayloadInfo {
+    pub fn of(payload: &[u8]) -> Self {
+        PayloadInfo {
+            len: payload.len() as u64,
+            hash: Sha256::digest(payload).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_event(seed: u8) -> Event {
+        let fill = |k: u8| -> [u8; 64] {
+            std::array::from_fn(|i| seed.wrapping_mul(31).wrapping_add(k ^ i as u8))
+        };
+        Event::from_raw(
+            seed,
+            fill(1)[..32].try_into().unwrap(),
+            fill(2)[..32].try_into().unwrap(),
+            u64::from(seed).wrapping_mul(0x9e37_79b9_7f4a_7c15),
+            fill(3)[..32].try_into().unwrap(),
+            fill(4),
+        )
+    }
+
+    #[test]
+    fn canonical_array_matches_bytes() {
+        for seed in [0u8, 9, 255] {
+            let ev = sample_event(seed);
+            assert_eq!(
+                ev.canonical_array().as_slice(),
+                ev.canonical_bytes().as_slice()
+            );
+        }
+    }
+
+    #[test]
+    fn wire_round_trip() {
+        for seed in [0u8, 1, 7, 200, 255] {
+            let ev = sample_event(seed);
+            let wire = ev.to_wire();
+            assert_eq!(&wire[..105], ev.canonical_bytes().as_slice());
+            assert_eq!(wire.as_slice(), ev.hash_material().as_slice());
+            let back = Event::from_wire(&wire).unwrap();
+            assert_eq!(back.to_wire(), wire);
+            assert_eq!(back.timestamp, ev.timestamp);
+        }
+    }
+
+    fn signed_event(version: u8, secret: &[u8; 32]) -> Event {
+        let sk = ed25519_dalek::SecretKey::from_bytes(secret).unwrap();
+        let author = DalekPublicKey::from(&sk).to_bytes();
+        let mut ev = Event::from_raw(version, ZERO_HASH, author, 5, [0x42; 32], [0u8; 64]);
+        ev.signature = crate::validation::compute_signature_with_secret(&ev, secret);
+        ev
+    }
+
+    #[test]
+    fn signing_input_is_domain_separated_from_v2() {
+        let secret = [9u8; 32];
+        let v1 = signed_event(1, &secret);
+        assert_eq!(v1.signing_input(), v1.canonical_bytes());
+        assert!(v1.verify_signature());
+
+        let v2 = signed_event(PROTOCOL_VERSION, &secret);
+        assert!(v2.signing_input().starts_with(EVENT_SIGNING_DOMAIN));
+        assert!(v2.verify_signature());
+
+        // An unprefixed signature over the same fields must not verify under version 2.
+        let sk = ed25519_dalek::SecretKey::from_bytes(&secret).unwrap();
+        let pk = DalekPublicKey::from(&sk);
+        let kp = ed25519_dalek::Keypair {
+            secret: sk,
+            public: pk,
+        };
+        let mut forged = v2.clone();
+        forged.signature = ed25519_dalek::Signer::sign(&kp, &v2.canonical_bytes()).to_bytes();
+        assert!(!forged.verify_signature());
+    }
+
+    #[test]
+    fn manifest_signature_does_not_verify_as_event() {
+        use crate::storage::{Manifest, sign_manifest_inplace, verify_manifest_signature};
+        let secret = [7u8; 32];
+        let mut ev = signed_event(PROTOCOL_VERSION, &secret);
+        let mut manifest = Manifest {
+            version: 1,
+            file_name: "f.bin".into(),
+            total_size: 0,
+            chunk_size: 1024,
+            chunks: Vec::new(),
+            chunk_suffix: None,
+            encryption: None,
+            chunk_runs: false,
+            metadata: Default::default(),
+            parity: None,
+            signers: Vec::new(),
+        };
+        sign_manifest_inplace(&mut manifest, &secret).unwrap();
+        let (signer, manifest_sig) = manifest.signers[0];
+        assert_eq!(signer, ev.author);
+        let event_sig = ev.signature;
+
+        ev.signature = manifest_sig;
+        assert!(!ev.verify_signature());
+
+        manifest.signers = vec![(signer, event_sig)];
+        assert!(verify_manifest_signature(&manifest).is_err());
+    }
+
+    #[test]
+    fn wire_rejects_wrong_length() {
+        let wire = sample_event(3).to_wire();
+        assert_eq!(
+            Event::from_wire(&wire[..WIRE_LEN - 1]).unwrap_err(),
+            EventError::TruncatedInput { len: WIRE_LEN - 1 }
+        );
+        let mut long = wire.to_vec();
+        long.push(0);
+        assert_eq!(
+            Event::from_wire(&long).unwrap_err(),
+            EventError::TrailingBytes { len: WIRE_LEN + 1 }
+        );
+    }
 }
@@ -1,7 +1,7 @@
 #![allow(clippy::not_unsafe_ptr_arg_deref)]
 
 use crate::event::{Event, Hash};
-use crate::ledger::{Ledger, LedgerLinkError};
+use crate::ledger::{Ledger, LinkError};
 use crate::scoring::{
     EquivocationPolicy, LocalPolicyKind, SybilConfig, SybilOverlay, TraceRecorder, TraceRow,
     select_preferred_tip_score, select_preferred_tip_score_sybil,
@@ -9,11 +9,12 @@ use crate::scoring::{
 use crate::storage;
 use crate::validation::{
     ValidationContext, ValidationError, compute_signature_with_secret, set_max_backward_skew,
-    validate_event,
+    validate_event, validate_event_at,
 };
 use std::cell::RefCell;
 use std::ffi::CStr;
 use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
 
 #[repr(C)]
 #[allow(non_camel_case_types)]
@@ -63,6 +64,9 @@ pub enum dvel_validation_result_t {
     DVEL_ERR_INVALID_VERSION = 1,
     DVEL_ERR_INVALID_SIGNATURE = 2,
     DVEL_ERR_TIMESTAMP_NON_MONOTONIC = 3,
+    DVEL_ERR_TIMESTAMP_TOO_FAR_AHEAD = 4,
+    DVEL_ERR_PAYLOAD_TOO_LARGE = 5,
+    DVEL_ERR_PAYLOAD_HASH_MISMATCH = 6,
 }
 
 #[repr(C)]
@@ -72,6 +76,9 @@ pub enum dvel_link_result_t {
     DVEL_LINK_OK = 0,
     DVEL_LINK_ERR_DUPLICATE = 1,
     DVEL_LINK_ERR_MISSING_PARENT = 2,
+    DVEL_LINK_ERR_TOO_MANY_TIPS = 3,
+    DVEL_LINK_ERR_INVALID_GENESIS = 4,
+    DVEL_LINK_ERR_ORPHANED = 5,
 }
 
 #[repr(C)]
@@ -99,6 +106,16 @@ pub struct dvel_merkle_root_t {
     pub has_value: bool,
 }
 
+/// Values accepted by `dvel_sybil_set_policy`. Passed as a `u32` and range-checked, never
+/// read as this enum from C memory.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum dvel_equivocation_policy_t {
+    DVEL_POLICY_QUARANTINE = 0,
+    DVEL_POLICY_SLASH = 1,
+}
+
 #[repr(C)]
 #[allow(non_camel_case_types)]
 #[derive(Clone, Copy)]
@@ -139,8 +156,7 @@ fn set_last_error(msg: impl Into<String>) {
     });
 }
 
-#[unsafe(no_mangle)]
-pub extern "C" fn dvel_storage_last_error(buf: *mut u8, buf_len: usize) -> usize {
+fn copy_last_error(buf: *mut u8, buf_len: usize) -> usize {
     let msg = LAST_ERROR.with(|e| e.borrow().clone());
     let bytes = msg.as_bytes();
     let copy_len = bytes.len().min(buf_len.saturating_sub(1));
@@ -153,6 +169,21 @@ pub extern "C" fn dvel_storage_last_error(buf: *mut u8, buf_len: usize) -> usize
     bytes.len()
 }
 
+/// Storage-era name for `dvel_last_error_message`; returns the message length without the NUL.
+#[unsafe(no_mangle)]
+pub extern "C" fn dvel_storage_last_error(buf: *mut u8, buf_len: usize) -> usize {
+    copy_last_error(buf, buf_len)
+}
+
+/// Copy this thread's most recent FFI error message into `buf` as NUL-terminated UTF-8.
+/// If `len` is too small the message is truncated, but `buf` is always NUL-terminated
+/// when `len > 0`. Returns the buffer size needed for the full message, including the NUL
+/// (1 when there is no error). Pass a null `buf` to query the size.
+#[unsafe(no_mangle)]
+pub extern "C" fn dvel_last_error_message(buf: *mut c_char, len: usize) -> usize {
+    copy_last_error(buf.cast(), len) + 1
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn dvel_hash_event_struct(ev: *const dvel_event_t) -> dvel_hash_t {
     if ev.is_null() {
@@ -193,21 +224,30 @@ pub extern "C" fn dvel_storage_chunk_file(
     sign: bool,
 ) -> bool {
     set_last_error("");
-    let input = match cstr_to_str(input_path) {
-        Ok(s) => std::path::Path::new(s),
+    let input = match cstr_to_path(input_path) {
+        Ok(p) => p,
         Err(e) => {
             set_last_error(format!("input_path: {}", e));
             return false;
         }
     };
-    let out_dir = match cstr_to_str(out_dir) {
-        Ok(s) => std::path::Path::new(s),
+    let out_dir = match cstr_to_path(out_dir) {
+        Ok(p) => p,
         Err(e) => {
             set_last_error(format!("out_dir: {}", e));
             return false;
         }
     };
+    chunk_file_at(&input, &out_dir, chunk_size, secret_key, sign)
+}
 
+fn chunk_file_at(
+    input: &Path,
+    out_dir: &Path,
+    chunk_size: usize,
+    secret_key: *const u8,
+    sign: bool,
+) -> bool {
     let mut manifest = match storage::chunk_file_to_dir(input, out_dir, chunk_size) {
         Ok(m) => m,
         Err(e) => {
@@ -248,28 +288,31 @@ pub extern "C" fn dvel_storage_download(
 ) -> bool {
     set_last_error("");
 
-    let mpath = match cstr_to_str(manifest_path) {
-        Ok(s) => std::path::Path::new(s),
+    let mpath = match cstr_to_path(manifest_path) {
+        Ok(p) => p,
         Err(e) => {
             set_last_error(format!("manifest_path: {}", e));
             return false;
         }
     };
-    let cdir = match cstr_to_str(chunk_dir) {
-        Ok(s) => std::path::Path::new(s),
+    let cdir = match cstr_to_path(chunk_dir) {
+        Ok(p) => p,
         Err(e) => {
             set_last_error(format!("chunk_dir: {}", e));
             return false;
         }
     };
-    let out = match cstr_to_str(output_path) {
-        Ok(s) => std::path::Path::new(s),
+    let out = match cstr_to_path(output_path) {
+        Ok(p) => p,
         Err(e) => {
             set_last_error(format!("output_path: {}", e));
             return false;
         }
     };
+    download_at(&mpath, &cdir, &out, expect_signer)
+}
 
+fn download_at(mpath: &Path, cdir: &Path, out: &Path, expect_signer: *const u8) -> bool {
     let manifest = match storage::read_manifest(mpath) {
         Ok(m) => m,
         Err(e) => {
@@ -279,14 +322,15 @@ pub extern "C" fn dvel_storage_download(
     };
 
     if !expect_signer.is_null() {
-        let expected = unsafe { std::slice::from_raw_parts(expect_signer, 32) };
-        if manifest.signer.as_ref().map(|s| &s[..]) != Some(expected) {
+        let mut expected = [0u8; 32];
+        expected.copy_from_slice(unsafe { std::slice::from_raw_parts(expect_signer, 32) });
+        if !manifest.has_signer(&expected) {
             set_last_error("signer mismatch");
             return false;
         }
     }
 
-    if manifest.signature.is_some()
+    if !manifest.signers.is_empty()
         && let Err(e) = storage::verify_manifest_signature(&manifest)
     {
         set_last_error(format!("{}", e));
@@ -306,6 +350,61 @@ pub extern "C" fn dvel_storage_download(
     true
 }
 
+/// `dvel_storage_chunk_file` with UTF-16 paths, for Windows callers with non-UTF-8 paths.
+#[cfg(windows)]
+#[unsafe(no_mangle)]
+pub extern "C" fn dvel_storage_chunk_file_w(
+    input_path: *const u16,
+    out_dir: *const u16,
+    chunk_size: usize,
+    secret_key: *const u8,
+    sign: bool,
+) -> bool {
+    set_last_error("");
+    let input = match wstr_to_path(input_path) {
+        Ok(p) => p,
+        Err(e) => {
+            set_last_error(format!("input_path: {}", e));
+            return false;
+        }
+    };
+    let out_dir = match wstr_to_path(out_dir) {
+        Ok(p) => p,
+        Err(e) => {
+            set_last_error(format!("out_dir: {}", e));
+            return false;
+        }
+    };
+    chunk_file_at(&input, &out_dir, chunk_size, secret_key, sign)
+}
+
+/// `dvel_storage_download` with UTF-16 paths, for Windows callers with non-UTF-8 paths.
+#[cfg(windows)]
+#[unsafe(no_mangle)]
+pub extern "C" fn dvel_storage_download_w(
+    manifest_path: *const u16,
+    chunk_dir: *const u16,
+    output_path: *const u16,
+    expect_signer: *const u8,
+) -> bool {
+    set_last_error("");
+    let mut paths = Vec::with_capacity(3);
+    for (label, ptr) in [
+        ("manifest_path", manifest_path),
+        ("chunk_dir", chunk_dir),
+        ("output_path", output_path),
+    ] {
+        match wstr_to_path(ptr) {
+            Ok(p) => paths.push(p),
+            Err(e) => {
+                set_last_error(format!("{}: {}", label, e));
+                return false;
+            }
+        }
+    }
+    download_at(&paths[0], &paths[1], &paths[2], expect_signer)
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn dvel_storage_manifest_hash(
     manifest_path: *const c_char,
@@ -316,14 +415,14 @@ pub extern "C" fn dvel_storage_manifest_hash(
         set_last_error("out_hash is null");
         return false;
     }
-    let mpath = match cstr_to_str(manifest_path) {
-        Ok(s) => std::path::Path::new(s),
+    let mpath = match cstr_to_path(manifest_path) {
+        Ok(p) => p,
         Err(e) => {
             set_last_error(format!("manifest_path: {}", e));
             return false;
         }
     };
-    match storage::manifest_hash_from_file(mpath) {
+    match storage::manifest_hash_from_file(&mpath) {
         Ok(h) => {
             unsafe { *out_hash = from_hash(&h) };
             true
@@ -345,14 +444,14 @@ pub extern "C" fn dvel_storage_chunk_merkle_root(
         set_last_error("out_hash is null");
         return false;
     }
-    let mpath = match cstr_to_str(manifest_path) {
-        Ok(s) => std::path::Path::new(s),
+    let mpath = match cstr_to_path(manifest_path) {
+        Ok(p) => p,
         Err(e) => {
             set_last_error(format!("manifest_path: {}", e));
             return false;
         }
     };
-    match storage::chunk_merkle_root_from_file(mpath) {
+    match storage::chunk_merkle_root_from_file(&mpath) {
         Ok(Some(h)) => {
             unsafe { *out_hash = from_hash(&h) };
             true
@@ -404,11 +503,41 @@ fn from_event(e: &Event) -> dvel_event_t {
     }
 }
 
-fn cstr_to_str<'a>(ptr: *const c_char) -> Result<&'a str, &'static str> {
+/// Path from a NUL-terminated byte string. On Unix any bytes are accepted (paths need
+/// not be UTF-8); elsewhere the bytes must be UTF-8 (use the `_w` entry points on Windows).
+fn cstr_to_path(ptr: *const c_char) -> Result<PathBuf, &'static str> {
+    if ptr.is_null() {
+        return Err("null pointer");
+    }
+    let bytes = unsafe { CStr::from_ptr(ptr) }.to_bytes();
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Ok(PathBuf::from(std::ffi::OsStr::from_bytes(bytes)))
+    }
+    #[cfg(not(unix))]
+    {
+        std::str::from_utf8(bytes)
+            .map(PathBuf::from)
+            .map_err(|_| "invalid utf-8")
+    }
+}
+
+/// Path from a NUL-terminated UTF-16 string (unpaired surrogates are kept, as Windows allows).
+#[cfg(windows)]
+fn wstr_to_path(ptr: *const u16) -> Result<PathBuf, &'static str> {
+    use std::os::windows::ffi::OsStringExt;
     if ptr.is_null() {
         return Err("null pointer");
     }
-    unsafe { CStr::from_ptr(ptr).to_str().map_err(|_| "invalid utf-8") }
+    let wide = unsafe {
+        let mut len = 0;
+        while *ptr.add(len) != 0 {
+            len += 1;
+        }
+        std::slice::from_raw_parts(ptr, len)
+    };
+    Ok(PathBuf::from(std::ffi::OsString::from_wide(wide)))
 }
 
 fn map_validation_error(err: ValidationError) -> dvel_validation_result_t {
@@ -418,13 +547,45 @@ fn map_validation_error(err: ValidationError) -> dvel_validation_result_t {
         ValidationError::TimestampNonMonotonic => {
             dvel_validation_result_t::DVEL_ERR_TIMESTAMP_NON_MONOTONIC
         }
+        ValidationError::TimestampTooFarAhead => {
+            dvel_validation_result_t::DVEL_ERR_TIMESTAMP_TOO_FAR_AHEAD
+        }
+        ValidationError::PayloadTooLarge => dvel_validation_result_t::DVEL_ERR_PAYLOAD_TOO_LARGE,
+        ValidationError::PayloadHashMismatch => {
+            dvel_validation_result_t::DVEL_ERR_PAYLOAD_HASH_MISMATCH
+        }
+    }
+}
+
+fn validation_error_message(err: &ValidationError) -> &'static str {
+    match err {
+        ValidationError::InvalidVersion => "unsupported event version",
+        ValidationError::InvalidSignature => "event signature does not verify",
+        ValidationError::TimestampNonMonotonic => "timestamp behind author's last beyond skew",
+        ValidationError::TimestampTooFarAhead => "timestamp too far ahead of now",
+        ValidationError::PayloadTooLarge => "payload exceeds max length",
+        ValidationError::PayloadHashMismatch => "payload does not match payload_hash",
+    }
+}
+
+fn link_error_message(err: LinkError) -> &'static str {
+    match err {
+        LinkError::Duplicate => "event already in ledger",
+        LinkError::MissingParent => "parent event not in ledger",
+        LinkError::TooManyTips { .. } => "event would exceed the ledger tip cap",
+        LinkError::InvalidGenesis => "ledger already has a genesis event",
+        LinkError::Orphaned => "parent event was pruned",
     }
 }
 
-fn map_link_error(err: LedgerLinkError) -> dvel_link_result_t {
+/// Variants line up with `LinkError::code()`.
+fn map_link_error(err: LinkError) -> dvel_link_result_t {
     match err {
-        LedgerLinkError::Duplicate => dvel_link_result_t::DVEL_LINK_ERR_DUPLICATE,
-        LedgerLinkError::MissingParent => dvel_link_result_t::DVEL_LINK_ERR_MISSING_PARENT,
+        LinkError::Duplicate => dvel_link_result_t::DVEL_LINK_ERR_DUPLICATE,
+        LinkError::MissingParent => dvel_link_result_t::DVEL_LINK_ERR_MISSING_PARENT,
+        LinkError::TooManyTips { .. } => dvel_link_result_t::DVEL_LINK_ERR_TOO_MANY_TIPS,
+        LinkError::InvalidGenesis => dvel_link_result_t::DVEL_LINK_ERR_INVALID_GENESIS,
+        LinkError::Orphaned => dvel_link_result_t::DVEL_LINK_ERR_ORPHANED,
     }
 }
 
@@ -469,6 +630,24 @@ pub extern "C" fn dvel_ledger_free(ledger: *mut Ledger) {
     }
 }
 
+/// Tip cap for `dvel_ledger_link_event`; 0 means unlimited (the default).
+#[unsafe(no_mangle)]
+pub extern "C" fn dvel_ledger_set_max_tips(ledger: *mut Ledger, max_tips: usize) {
+    if ledger.is_null() {
+        return;
+    }
+    unsafe { &mut *ledger }.set_max_tips((max_tips != 0).then_some(max_tips));
+}
+
+/// Reject a second genesis event in `dvel_ledger_link_event` when `enabled`.
+#[unsafe(no_mangle)]
+pub extern "C" fn dvel_ledger_set_single_genesis(ledger: *mut Ledger, enabled: bool) {
+    if ledger.is_null() {
+        return;
+    }
+    unsafe { &mut *ledger }.set_single_genesis(enabled);
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn dvel_ledger_add_event(
     ledger: *mut Ledger,
@@ -492,6 +671,7 @@ pub extern "C" fn dvel_ledger_link_event(
     out_hash: *mut dvel_hash_t,
 ) -> dvel_link_result_t {
     if ledger.is_null() || event.is_null() {
+        set_last_error("ledger or event is null");
         return dvel_link_result_t::DVEL_LINK_ERR_MISSING_PARENT;
     }
 
@@ -500,7 +680,10 @@ pub extern "C" fn dvel_ledger_link_event(
         let e = to_event(&*event);
         match l.try_add_event(e) {
             Ok(hash) => (dvel_link_result_t::DVEL_LINK_OK, Some(hash)),
-            Err(err) => (map_link_error(err), None),
+            Err(err) => {
+                set_last_error(link_error_message(err));
+                (map_link_error(err), None)
+            }
         }
     };
 
@@ -555,8 +738,31 @@ pub extern "C" fn dvel_set_max_backward_skew(skew: u64) {
 pub extern "C" fn dvel_validate_event(
     event: *const dvel_event_t,
     ctx: *mut dvel_validation_ctx_t,
+) -> dvel_validation_result_t {
+    validate_with_ctx(event, ctx, validate_event)
+}
+
+/// Opt-in forward bound: also rejects timestamps more than `max_forward_skew` ahead of `now`.
+#[unsafe(no_mangle)]
+pub extern "C" fn dvel_validate_event_at(
+    event: *const dvel_event_t,
+    ctx: *mut dvel_validation_ctx_t,
+    now: u64,
+    max_forward_skew: u64,
+) -> dvel_validation_result_t {
+    validate_with_ctx(event, ctx, |ev, vctx| {
+        vctx.max_forward_skew = max_forward_skew;
+        validate_event_at(ev, vctx, now)
+    })
+}
+
+fn validate_with_ctx(
+    event: *const dvel_event_t,
+    ctx: *mut dvel_validation_ctx_t,
+    check: impl FnOnce(&Event, &mut ValidationContext) -> Result<(), ValidationError>,
 ) -> dvel_validation_result_t {
     if event.is_null() || ctx.is_null() {
+        set_last_error("event or ctx is null");
         return dvel_validation_result_t::DVEL_ERR_INVALID_VERSION;
     }
 
@@ -564,19 +770,23 @@ pub extern "C" fn dvel_validate_event(
         (
             ValidationContext {
                 last_timestamp: (*ctx).last_timestamp,
+                ..ValidationContext::new()
             },
             ctx,
         )
     };
 
-    match validate_event(&unsafe { to_event(&*event) }, &mut vctx) {
+    match check(&unsafe { to_event(&*event) }, &mut vctx) {
         Ok(()) => {
             unsafe {
                 (*c_ptr).last_timestamp = vctx.last_timestamp;
             }
             dvel_validation_result_t::DVEL_OK
         }
-        Err(err) => map_validation_error(err),
+        Err(err) => {
+            set_last_error(validation_error_message(&err));
+            map_validation_error(err)
+        }
     }
 }
 
@@ -671,17 +881,108 @@ pub extern "C" fn dvel_sybil_overlay_set_config(
         return;
     }
     unsafe {
-        let o = &mut *overlay;
-        let c = &*cfg;
-        o.cfg = SybilConfig {
-            warmup_ticks: c.warmup_ticks,
-            quarantine_ticks: c.quarantine_ticks,
-            policy: EquivocationPolicy::Quarantine,
-            fixed_point_scale: c.fixed_point_scale,
-            max_link_walk: c.max_link_walk,
-            slash_percent: 5, // Default 5% economic penalty
+        let policy = (*overlay).cfg.policy;
+        (*overlay).cfg = to_sybil_config(&*cfg, policy);
+    }
+}
+
+/// `dvel_sybil_config_t` has no policy field (its layout predates it); callers keep the
+/// overlay's current policy, and `dvel_sybil_set_policy` changes it.
+fn to_sybil_config(c: &dvel_sybil_config_t, policy: EquivocationPolicy) -> SybilConfig {
+    SybilConfig {
+        warmup_ticks: c.warmup_ticks,
+        quarantine_ticks: c.quarantine_ticks,
+        policy,
+        fixed_point_scale: c.fixed_point_scale,
+        max_link_walk: c.max_link_walk,
+    }
+}
+
+/// Set the equivocation policy to a `dvel_equivocation_policy_t` value. False (with a
+/// last-error message) on a null overlay or an unknown value; the overlay is unchanged.
+#[unsafe(no_mangle)]
+pub extern "C" fn dvel_sybil_set_policy(overlay: *mut SybilOverlay, policy: u32) -> bool {
+    set_last_error("");
+    if overlay.is_null() {
+        set_last_error("overlay is null");
+        return false;
+    }
+    let policy = match policy {
+        p if p == dvel_equivocation_policy_t::DVEL_POLICY_QUARANTINE as u32 => {
+            EquivocationPolicy::Quarantine
+        }
+        p if p == dvel_equivocation_policy_t::DVEL_POLICY_SLASH as u32 => EquivocationPolicy::Slash,
+        p => {
+            set_last_error(format!("unknown equivocation policy {}", p));
+            return false;
+        }
+    };
+    unsafe { (*overlay).cfg.policy = policy };
+    true
+}
+
+/// Overlay configured from `cfg` (default config if null), with the default quarantine
+/// policy; see `dvel_sybil_set_policy`. Free with `dvel_sybil_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn dvel_sybil_new(cfg: *const dvel_sybil_config_t) -> *mut SybilOverlay {
+    let cfg = if cfg.is_null() {
+        SybilConfig::default()
+    } else {
+        to_sybil_config(unsafe { &*cfg }, SybilConfig::default().policy)
+    };
+    Box::into_raw(Box::new(SybilOverlay::new(cfg)))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn dvel_sybil_free(ptr: *mut SybilOverlay) {
+    dvel_sybil_overlay_free(ptr)
+}
+
+/// Observe `ev` (already linked into `ledger`) as seen by `node_id` at tick `now`.
+/// `hash` may be null, in which case it is computed from `ev`. False (with a last-error
+/// message) on null handles.
+#[unsafe(no_mangle)]
+pub extern "C" fn dvel_sybil_observe_event(
+    overlay: *mut SybilOverlay,
+    ledger: *const Ledger,
+    now: u64,
+    node_id: u32,
+    ev: *const dvel_event_t,
+    hash: *const dvel_hash_t,
+) -> bool {
+    set_last_error("");
+    if overlay.is_null() {
+        set_last_error("overlay is null");
+        return false;
+    }
+    if ledger.is_null() {
+        set_last_error("ledger is null");
+        return false;
+    }
+    if ev.is_null() {
+        set_last_error("ev is null");
+        return false;
+    }
+    unsafe {
+        let e = to_event(&*ev);
+        let h = if hash.is_null() {
+            Ledger::hash_event(&e)
+        } else {
+            to_hash(&*hash)
         };
+        (*overlay).observe_event(&*ledger, now, node_id, &e, h);
     }
+    true
+}
+
+/// Fixed-point weight of `author` at tick `now` (0 for a null overlay).
+#[unsafe(no_mangle)]
+pub extern "C" fn dvel_sybil_author_weight(
+    overlay: *const SybilOverlay,
+    author: dvel_pubkey_t,
+    now: u64,
+) -> u64 {
+    dvel_sybil_overlay_author_weight_fp(overlay, now, author)
 }
 
 // Trace recorder (for external proof systems)
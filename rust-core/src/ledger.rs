@@ -1,24 +1,203 @@
 // Linkage-aware ledger (single-parent, fork-legal).
 // Separates validity from linkage: this module only enforces parent existence + duplicate rules and derives tips.
 
-use crate::event::{Event, Hash};
+use crate::event::{Event, Hash, WIRE_LEN};
+use crate::scoring::SybilOverlay;
 use sha2::{Digest, Sha256};
 use std::collections::BTreeSet;
 use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Ledger file header; followed by a u64 LE count and the prune-boundary hashes, a u64 LE
+/// count and the pruned hashes, then a u64 LE event count and `count` wire-encoded events.
+const LEDGER_FILE_MAGIC: &[u8; 8] = b"dvel-lg2";
 
 /// Genesis marker: prev_hash = all-zeroes means no parent.
 pub const ZERO_HASH: Hash = [0u8; 32];
 
+/// Why `try_add_event` refused an event. `code()` is the matching `dvel_link_result_t`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum LedgerLinkError {
+pub enum LinkError {
+    /// An event with the same hash is already stored.
     Duplicate,
+    /// The parent is neither stored, a prune boundary, nor known to have been pruned.
     MissingParent,
+    /// The event would open a new tip while `max_tips` tips already exist.
+    TooManyTips { limit: usize },
+    /// A second genesis event under the single-genesis policy.
+    InvalidGenesis,
+    /// The parent was removed by `prune` (below the boundary); the event can never link.
+    Orphaned,
+}
+
+impl LinkError {
+    /// Stable numeric code; equals the FFI `dvel_link_result_t` value (0 is success).
+    pub fn code(&self) -> u32 {
+        match self {
+            LinkError::Duplicate => 1,
+            LinkError::MissingParent => 2,
+            LinkError::TooManyTips { .. } => 3,
+            LinkError::InvalidGenesis => 4,
+            LinkError::Orphaned => 5,
+        }
+    }
+}
+
+/// Former name of `LinkError`.
+pub type LedgerLinkError = LinkError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerError {
+    /// The checkpoint passed to `prune` is not stored.
+    UnknownCheckpoint,
+    /// More than one stored event has `prev_hash == ZERO_HASH`.
+    MultipleGenesis { count: usize },
+}
+
+/// Memoised `merkle_root` state: the sorted leaf list plus every tree level above it.
+/// Inserts and removals are only buffered, so linking stays amortised O(1); the next root
+/// query merges them into the sorted leaves (O(n + k log k) for k buffered inserts) and
+/// rehashes the nodes from the lowest changed index rightwards on each level. Leaves are
+/// sorted, so an insert shifts every later pairing; this is the least work that keeps the
+/// commitment unchanged.
+#[derive(Debug, Default, Clone)]
+struct MerkleCache {
+    leaves: Vec<Hash>,
+    /// Inserted since the last query, unsorted; may repeat a stored leaf.
+    pending: Vec<Hash>,
+    /// Removed since the last query; each is in `leaves` or `pending`.
+    removed: HashSet<Hash>,
+    /// levels[0] pairs the leaves; the last level holds the root (empty for <= 1 leaf).
+    levels: Vec<Vec<Hash>>,
+    dirty_from: Option<usize>,
+}
+
+impl MerkleCache {
+    fn mark(&mut self, idx: usize) {
+        self.dirty_from = Some(self.dirty_from.map_or(idx, |d| d.min(idx)));
+    }
+
+    fn insert(&mut self, leaf: Hash) {
+        // Re-adding a leaf removed since the last query cancels the removal.
+        if !self.removed.remove(&leaf) {
+            self.pending.push(leaf);
+        }
+    }
+
+    fn remove(&mut self, leaf: &Hash) {
+        self.removed.insert(*leaf);
+    }
+
+    /// Apply buffered removals and inserts to `leaves`, marking the lowest changed index.
+    fn flush(&mut self) {
+        if !self.removed.is_empty() {
+            let removed = std::mem::take(&mut self.removed);
+            self.pending.retain(|h| !removed.contains(h));
+            if let Some(first) = self.leaves.iter().position(|h| removed.contains(h)) {
+                self.leaves.retain(|h| !removed.contains(h));
+                self.mark(first);
+            }
+        }
+        if self.pending.is_empty() {
+            return;
+        }
+        let mut pending = std::mem::take(&mut self.pending);
+        pending.sort_unstable();
+        pending.dedup();
+        let old = std::mem::take(&mut self.leaves);
+        let mut merged = Vec::with_capacity(old.len() + pending.len());
+        let mut first_new = None;
+        let (mut i, mut j) = (0, 0);
+        while j < pending.len() {
+            if i < old.len() && old[i] <= pending[j] {
+                if old[i] == pending[j] {
+                    j += 1;
+                }
+                merged.push(old[i]);
+                i += 1;
+            } else {
+                first_new.get_or_insert(merged.len());
+                merged.push(pending[j]);
+                j += 1;
+            }
+        }
+        merged.extend_from_slice(&old[i..]);
+        self.leaves = merged;
+        if let Some(idx) = first_new {
+            self.mark(idx);
+        }
+    }
+
+    fn root(&mut self) -> Option<Hash> {
+        self.flush();
+        if let Some(mut dirty) = self.dirty_from.take() {
+            let mut lvl = 0;
+            loop {
+                let below_len = if lvl == 0 {
+                    self.leaves.len()
+                } else {
+                    self.levels[lvl - 1].len()
+                };
+                if below_len <= 1 {
+                    break;
+                }
+                if lvl == self.levels.len() {
+                    self.levels.push(Vec::new());
+                }
+                let (lower, upper) = self.levels.split_at_mut(lvl);
+                let below = if lvl == 0 {
+                    &self.leaves
+                } else {
+                    &lower[lvl - 1]
+                };
+                let up = &mut upper[0];
+                let parent = dirty / 2;
+                up.truncate(parent);
+                for i in (parent * 2..below_len).step_by(2) {
+                    let (a, b) = (below[i], *below.get(i + 1).unwrap_or(&below[i]));
+                    let mut hasher = Sha256::new();
+                    hasher.update(a);
+                    hasher.update(b);
+                    up.push(hasher.finalize().into());
+                }
+                dirty = parent;
+                lvl += 1;
+            }
+            self.levels.truncate(lvl);
+        }
+        match self.levels.last() {
+            Some(top) => top.first().copied(),
+            None => self.leaves.first().copied(),
+        }
+    }
+}
+
+/// Per-tip summary for fork-choice: height (genesis = 0) and cumulative chain weight.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TipInfo {
+    pub hash: Hash,
+    pub height: u64,
+    pub cumulative_weight: u64,
 }
 
 #[derive(Debug)]
 pub struct Ledger {
     events: HashMap<Hash, Event>,
     tips: HashSet<Hash>,
+    /// Pruned parents of retained checkpoints; accepted as roots by `try_add_event`.
+    prune_boundary: HashSet<Hash>,
+    /// Hashes removed by `prune` (persisted by `save`); children of these are `Orphaned`
+    /// rather than `MissingParent`.
+    pruned: HashSet<Hash>,
+    /// Tip cap enforced by `try_add_event` (None = unlimited).
+    max_tips: Option<usize>,
+    /// Reject a second genesis event in `try_add_event`.
+    single_genesis: bool,
+    /// Incremental `merkle_root` state over `events`' keys.
+    merkle: Mutex<MerkleCache>,
 }
 
 impl Ledger {
@@ -26,13 +205,37 @@ impl Ledger {
         Ledger {
             events: HashMap::new(),
             tips: HashSet::new(),
+            prune_boundary: HashSet::new(),
+            pruned: HashSet::new(),
+            max_tips: None,
+            single_genesis: false,
+            merkle: Mutex::new(MerkleCache::default()),
         }
     }
 
+    fn merkle_cache(&self) -> std::sync::MutexGuard<'_, MerkleCache> {
+        // The cache is rebuilt from `dirty_from` on every query; a poisoned lock holds no
+        // half-applied state worth discarding.
+        self.merkle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Cap the number of tips: an event that would open a new tip (fork or extra root)
+    /// while `limit` tips exist is `TooManyTips`. Extending a tip is always allowed.
+    pub fn set_max_tips(&mut self, limit: Option<usize>) {
+        self.max_tips = limit;
+    }
+
+    /// Enforce `validate_single_genesis` at link time: a genesis event is `InvalidGenesis`
+    /// if one is already stored.
+    pub fn set_single_genesis(&mut self, enabled: bool) {
+        self.single_genesis = enabled;
+    }
+
     pub fn hash_event(event: &Event) -> Hash {
-        let bytes = event.hash_material();
+        // Same digest as SHA256(hash_material()), without the intermediate Vec.
         let mut hasher = Sha256::new();
-        hasher.update(&bytes);
+        hasher.update(event.canonical_array());
+        hasher.update(event.signature);
         hasher.finalize().into()
     }
 
@@ -40,6 +243,7 @@ impl Ledger {
     pub fn add_event(&mut self, event: Event) -> Hash {
         let hash = Self::hash_event(&event);
         self.events.insert(hash, event.clone());
+        self.merkle_cache().insert(hash);
 
         // Update tips (legacy): remove parent tip (even if ZERO_HASH) and insert new tip.
         if event.prev_hash != ZERO_HASH {
@@ -52,20 +256,39 @@ impl Ledger {
 
     /// Linkage-aware add:
     /// - Reject duplicate hash
-    /// - Reject missing parent unless genesis (prev_hash == ZERO_HASH)
+    /// - Reject missing parent unless genesis (prev_hash == ZERO_HASH) or a prune boundary;
+    ///   a parent removed by `prune` is `Orphaned`
+    /// - Apply the single-genesis and tip-cap policies, if set
     /// - Update tips deterministically on success
-    pub fn try_add_event(&mut self, event: Event) -> Result<Hash, LedgerLinkError> {
+    pub fn try_add_event(&mut self, event: Event) -> Result<Hash, LinkError> {
         let hash = Self::hash_event(&event);
 
         if self.events.contains_key(&hash) {
-            return Err(LedgerLinkError::Duplicate);
+            return Err(LinkError::Duplicate);
+        }
+
+        if event.prev_hash == ZERO_HASH {
+            if self.single_genesis && self.events.values().any(|e| e.prev_hash == ZERO_HASH) {
+                return Err(LinkError::InvalidGenesis);
+            }
+        } else if !self.events.contains_key(&event.prev_hash)
+            && !self.prune_boundary.contains(&event.prev_hash)
+        {
+            if self.pruned.contains(&event.prev_hash) {
+                return Err(LinkError::Orphaned);
+            }
+            return Err(LinkError::MissingParent);
         }
 
-        if event.prev_hash != ZERO_HASH && !self.events.contains_key(&event.prev_hash) {
-            return Err(LedgerLinkError::MissingParent);
+        if let Some(limit) = self.max_tips
+            && !self.tips.contains(&event.prev_hash)
+            && self.tips.len() >= limit
+        {
+            return Err(LinkError::TooManyTips { limit });
         }
 
         self.events.insert(hash, event.clone());
+        self.merkle_cache().insert(hash);
 
         if event.prev_hash != ZERO_HASH {
             self.tips.remove(&event.prev_hash);
@@ -89,15 +312,323 @@ impl Ledger {
         self.tips.clone()
     }
 
+    /// Hashes of all stored genesis events (`prev_hash == ZERO_HASH`), sorted.
+    /// Events linked to a prune boundary are roots but not genesis, so they are excluded.
+    pub fn genesis_events(&self) -> Vec<Hash> {
+        let mut out: Vec<Hash> = self
+            .events
+            .iter()
+            .filter(|(_, ev)| ev.prev_hash == ZERO_HASH)
+            .map(|(h, _)| *h)
+            .collect();
+        out.sort_unstable();
+        out
+    }
+
+    /// Single-genesis policy check for permissioned setups: at most one genesis event.
+    pub fn validate_single_genesis(&self) -> Result<(), LedgerError> {
+        match self.genesis_events().len() {
+            0 | 1 => Ok(()),
+            count => Err(LedgerError::MultipleGenesis { count }),
+        }
+    }
+
+    /// Chain from `hash` back to its root: `[hash, parent, ..., genesis]`. The walk stops at
+    /// `ZERO_HASH` or at a parent that is not stored. None if `hash` is unknown, or if the walk
+    /// exceeds the stored event count (only possible with a corrupted store containing a cycle).
+    pub fn path_to_root(&self, hash: &Hash) -> Option<Vec<Hash>> {
+        let mut path = Vec::new();
+        let mut cur = *hash;
+        for _ in 0..self.events.len() {
+            let e = self.events.get(&cur)?;
+            path.push(cur);
+            if e.prev_hash == ZERO_HASH || !self.events.contains_key(&e.prev_hash) {
+                return Some(path);
+            }
+            cur = e.prev_hash;
+        }
+        None
+    }
+
+    /// Strict ancestors of `hash`, nearest first. Empty for unknown events, genesis events,
+    /// and walks that hit the cycle cap (see `path_to_root`).
+    pub fn ancestors(&self, hash: &Hash) -> Vec<Hash> {
+        match self.path_to_root(hash) {
+            Some(mut path) => {
+                path.remove(0);
+                path
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// True iff `ancestor` is a strict ancestor of `descendant` (an event is not its own
+    /// ancestor). Bounded like `path_to_root`.
+    pub fn is_ancestor(&self, ancestor: &Hash, descendant: &Hash) -> bool {
+        let Some(mut cur) = self.events.get(descendant).map(|e| e.prev_hash) else {
+            return false;
+        };
+        for _ in 0..self.events.len() {
+            if cur == *ancestor {
+                return self.events.contains_key(ancestor);
+            }
+            match self.events.get(&cur) {
+                Some(e) if cur != ZERO_HASH => cur = e.prev_hash,
+                _ => return false,
+            }
+        }
+        false
+    }
+
+    /// Drop finalized history below `keep_from`: every strict ancestor of the checkpoint that
+    /// no current tip reaches except through `keep_from` is removed. The checkpoint's parent
+    /// becomes a prune boundary, so it (and new children of it) link as roots; children of
+    /// deeper removed ancestors are rejected as `Orphaned`. Returns the number removed.
+    pub fn prune(&mut self, keep_from: &Hash) -> Result<usize, LedgerError> {
+        let Some(checkpoint) = self.events.get(keep_from) else {
+            return Err(LedgerError::UnknownCheckpoint);
+        };
+        let boundary = checkpoint.prev_hash;
+
+        // Everything reachable from a tip without passing through the checkpoint stays.
+        let mut reachable: HashSet<Hash> = HashSet::new();
+        for tip in &self.tips {
+            let mut cur = *tip;
+            while cur != *keep_from && reachable.insert(cur) {
+                match self.events.get(&cur) {
+                    Some(e) => cur = e.prev_hash,
+                    None => break,
+                }
+            }
+        }
+
+        let doomed: Vec<Hash> = self
+            .ancestors(keep_from)
+            .into_iter()
+            .filter(|h| !reachable.contains(h))
+            .collect();
+        for h in &doomed {
+            self.events.remove(h);
+            self.merkle_cache().remove(h);
+            self.pruned.insert(*h);
+        }
+        if boundary != ZERO_HASH && !self.events.contains_key(&boundary) {
+            self.prune_boundary.insert(boundary);
+        }
+        Ok(doomed.len())
+    }
+
+    /// Event hashes present only in `self` and only in `other`, each sorted by hash.
+    /// One pass over each event map; only the (usually small) differences are sorted.
+    pub fn difference(&self, other: &Ledger) -> (Vec<Hash>, Vec<Hash>) {
+        let only = |a: &Ledger, b: &Ledger| {
+            let mut v: Vec<Hash> = a
+                .events
+                .keys()
+                .filter(|h| !b.events.contains_key(*h))
+                .copied()
+                .collect();
+            v.sort();
+            v
+        };
+        (only(self, other), only(other, self))
+    }
+
+    /// Tips with height and cumulative weight, sorted by hash.
+    /// Weight sums `overlay.author_weight_fp(now, author)` along the chain; without an
+    /// overlay every event counts 1 (unit weight). Walks are capped at the event count.
+    pub fn tip_infos(&self, overlay: Option<&SybilOverlay>, now: u64) -> Vec<TipInfo> {
+        let mut tips: Vec<Hash> = self.tips.iter().copied().collect();
+        tips.sort();
+        tips.into_iter()
+            .map(|tip| {
+                let mut height: u64 = 0;
+                let mut weight: u64 = 0;
+                let mut cur = tip;
+                for _ in 0..self.events.len() {
+                    let Some(e) = self.events.get(&cur) else {
+                        break;
+                    };
+                    let w = match overlay {
+                        Some(o) => o.author_weight_fp(now, e.author),
+                        None => 1,
+                    };
+                    weight = weight.saturating_add(w);
+                    if e.prev_hash == ZERO_HASH || !self.events.contains_key(&e.prev_hash) {
+                        break;
+                    }
+                    height += 1;
+                    cur = e.prev_hash;
+                }
+                TipInfo {
+                    hash: tip,
+                    height,
+                    cumulative_weight: weight,
+                }
+            })
+            .collect()
+    }
+
+    /// Height of every stored event (genesis = 0). An event whose parent is not stored is
+    /// treated as a root; memoised so each event is walked once. Walks are capped at the
+    /// event count like the ancestry queries.
+    fn heights(&self) -> HashMap<Hash, u64> {
+        let mut heights: HashMap<Hash, u64> = HashMap::with_capacity(self.events.len());
+        for &start in self.events.keys() {
+            let mut path = Vec::new();
+            let mut cur = start;
+            let mut base = loop {
+                if let Some(&h) = heights.get(&cur) {
+                    break h + 1;
+                }
+                let Some(e) = self.events.get(&cur) else {
+                    break 0;
+                };
+                path.push(cur);
+                if e.prev_hash == ZERO_HASH || path.len() > self.events.len() {
+                    break 0;
+                }
+                cur = e.prev_hash;
+            };
+            for h in path.into_iter().rev() {
+                heights.insert(h, base);
+                base += 1;
+            }
+        }
+        heights
+    }
+
+    /// Write all events to `path` in (height, hash) order so every parent precedes its
+    /// children. Format: magic, u64 LE boundary count and prune-boundary hashes (sorted),
+    /// u64 LE pruned count and pruned hashes (sorted), u64 LE event count, then
+    /// `Event::to_wire` records.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let heights = self.heights();
+        let mut order: Vec<(u64, Hash)> = heights.into_iter().map(|(h, ht)| (ht, h)).collect();
+        order.sort();
+
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(LEDGER_FILE_MAGIC)?;
+        for set in [&self.prune_boundary, &self.pruned] {
+            let mut sorted: Vec<&Hash> = set.iter().collect();
+            sorted.sort();
+            out.write_all(&(sorted.len() as u64).to_le_bytes())?;
+            for h in sorted {
+                out.write_all(h)?;
+            }
+        }
+        out.write_all(&(order.len() as u64).to_le_bytes())?;
+        for (_, h) in &order {
+            out.write_all(&self.events[h].to_wire())?;
+        }
+        out.flush()
+    }
+
+    /// Rebuild a ledger saved by `save`, re-linking every event through `try_add_event`.
+    /// Malformed files, link violations (duplicates, missing parents) and prune boundaries
+    /// that no stored event names as its parent are `InvalidData`.
+    pub fn load(path: &Path) -> io::Result<Ledger> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+
+        let Some(mut rest) = bytes.strip_prefix(LEDGER_FILE_MAGIC.as_slice()) else {
+            return Err(invalid("not a ledger file".into()));
+        };
+        let read_count = |rest: &mut &[u8]| -> io::Result<u64> {
+            let (n, tail) = rest
+                .split_first_chunk::<8>()
+                .ok_or_else(|| invalid("truncated ledger file".into()))?;
+            *rest = tail;
+            Ok(u64::from_le_bytes(*n))
+        };
+
+        let mut ledger = Ledger::new();
+        for set in [&mut ledger.prune_boundary, &mut ledger.pruned] {
+            let n = read_count(&mut rest)?;
+            for _ in 0..n {
+                let (h, tail) = rest
+                    .split_first_chunk::<32>()
+                    .ok_or_else(|| invalid("truncated ledger file".into()))?;
+                set.insert(*h);
+                rest = tail;
+            }
+        }
+
+        let count = read_count(&mut rest)?;
+        if rest.len() % WIRE_LEN != 0 || (rest.len() / WIRE_LEN) as u64 != count {
+            return Err(invalid(format!(
+                "ledger file length does not match {} events",
+                count
+            )));
+        }
+        for (idx, rec) in rest.chunks_exact(WIRE_LEN).enumerate() {
+            let ev = Event::from_wire(rec).map_err(|e| invalid(format!("event {}: {}", idx, e)))?;
+            ledger
+                .try_add_event(ev)
+                .map_err(|e| invalid(format!("event {}: {:?}", idx, e)))?;
+        }
+
+        // A boundary only exists to root the children of a pruned parent; one that roots
+        // nothing (or names a stored event) could admit arbitrary parentless events.
+        let parents: HashSet<Hash> = ledger.events.values().map(|e| e.prev_hash).collect();
+        for h in &ledger.prune_boundary {
+            if !parents.contains(h) || ledger.events.contains_key(h) {
+                return Err(invalid(format!(
+                    "unreferenced prune boundary {}",
+                    hex::encode(h)
+                )));
+            }
+        }
+        if let Some(h) = ledger
+            .pruned
+            .iter()
+            .find(|h| ledger.events.contains_key(*h))
+        {
+            return Err(invalid(format!("pruned hash {} is stored", hex::encode(h))));
+        }
+        Ok(ledger)
+    }
+
     /// Deterministic Merkle root over all event hashes (lexicographically sorted leaves).
-    /// None iff ledger is empty.
+    /// After `prune` it covers retained events only. None iff ledger is empty.
+    /// Maintained incrementally: repeated calls are O(1), and after a change only the
+    /// nodes to the right of the lowest changed leaf are rehashed.
     pub fn merkle_root(&self) -> Option<Hash> {
-        if self.events.is_empty() {
+        self.merkle_cache().root()
+    }
+
+    /// `storage::merkle_root_v2` over all event hashes: same leaf set as `merkle_root`, but
+    /// domain-separated and without odd-node duplication. None iff ledger is empty.
+    pub fn merkle_root_v2(&self) -> Option<Hash> {
+        crate::storage::merkle_root_v2(&self.events.keys().copied().collect::<Vec<_>>())
+    }
+
+    /// True iff the current Merkle root equals an externally published commitment.
+    /// An empty ledger has no root and never matches.
+    pub fn verify_root(&self, expected: Hash) -> bool {
+        self.merkle_root() == Some(expected)
+    }
+
+    /// Merkle root over the events at height <= `height` (genesis = 0), i.e. the root the
+    /// ledger had at that checkpoint if events arrived in height order. Heights count from
+    /// the retained roots, so after `prune` they are relative to the prune boundary.
+    /// None if no event is that low.
+    pub fn root_at_height(&self, height: u64) -> Option<Hash> {
+        let heights = self.heights();
+        Self::merkle_root_of(
+            heights
+                .into_iter()
+                .filter(|&(_, h)| h <= height)
+                .map(|(hash, _)| hash),
+        )
+    }
+
+    fn merkle_root_of(leaves: impl Iterator<Item = Hash>) -> Option<Hash> {
+        let mut level: Vec<Hash> = BTreeSet::from_iter(leaves).into_iter().collect();
+        if level.is_empty() {
             return None;
         }
-        let mut level: Vec<Hash> = BTreeSet::from_iter(self.events.keys().cloned())
-            .into_iter()
-            .collect();
 
         while level.len() > 1 {
             let mut next: Vec<Hash> = Vec::with_capacity(level.len().div_ceil(2));
@@ -123,8 +654,460 @@ impl Ledger {
     }
 }
 
+impl Clone for Ledger {
+    fn clone(&self) -> Self {
+        Ledger {
+            events: self.events.clone(),
+            tips: self.tips.clone(),
+            prune_boundary: self.prune_boundary.clone(),
+            pruned: self.pruned.clone(),
+            max_tips: self.max_tips,
+            single_genesis: self.single_genesis,
+            merkle: Mutex::new(self.merkle_cache().clone()),
+        }
+    }
+}
+
 impl Default for Ledger {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::PublicKey;
+    use crate::scoring::SybilConfig;
+
+    fn ev(prev: Hash, author: PublicKey, ts: u64, tag: u8) -> Event {
+        Event::new(prev, author, ts, [tag; 32], [tag; 64])
+    }
+
+    #[test]
+    fn merkle_root_v2_covers_same_events() {
+        let mut ledger = Ledger::new();
+        assert_eq!(ledger.merkle_root_v2(), None);
+        let g = ledger
+            .try_add_event(ev(ZERO_HASH, [1u8; 32], 1, 1))
+            .unwrap();
+        let a = ledger.try_add_event(ev(g, [1u8; 32], 2, 2)).unwrap();
+        ledger.try_add_event(ev(a, [1u8; 32], 3, 3)).unwrap();
+        let leaves: Vec<Hash> = ledger.hashes_set().into_iter().collect();
+        assert_eq!(
+            ledger.merkle_root_v2(),
+            crate::storage::merkle_root_v2(&leaves)
+        );
+        assert_ne!(ledger.merkle_root_v2(), ledger.merkle_root());
+    }
+
+    #[test]
+    fn genesis_events_enumerated_and_policy_checked() {
+        let mut ledger = Ledger::new();
+        assert_eq!(ledger.validate_single_genesis(), Ok(()));
+        let g1 = ledger
+            .try_add_event(ev(ZERO_HASH, [1u8; 32], 1, 1))
+            .unwrap();
+        ledger.try_add_event(ev(g1, [1u8; 32], 2, 2)).unwrap();
+        assert_eq!(ledger.genesis_events(), vec![g1]);
+        assert_eq!(ledger.validate_single_genesis(), Ok(()));
+
+        let g2 = ledger
+            .try_add_event(ev(ZERO_HASH, [2u8; 32], 3, 3))
+            .unwrap();
+        let mut expected = vec![g1, g2];
+        expected.sort();
+        assert_eq!(ledger.genesis_events(), expected);
+        assert_eq!(
+            ledger.validate_single_genesis(),
+            Err(LedgerError::MultipleGenesis { count: 2 })
+        );
+    }
+
+    #[test]
+    fn ancestry_queries_on_forked_graph() {
+        // g <- a <- b <- c1
+        //           \<- c2 <- d2
+        let author = [4u8; 32];
+        let mut ledger = Ledger::new();
+        let g = ledger.try_add_event(ev(ZERO_HASH, author, 1, 1)).unwrap();
+        let a = ledger.try_add_event(ev(g, author, 2, 2)).unwrap();
+        let b = ledger.try_add_event(ev(a, author, 3, 3)).unwrap();
+        let c1 = ledger.try_add_event(ev(b, author, 4, 4)).unwrap();
+        let c2 = ledger.try_add_event(ev(b, author, 4, 5)).unwrap();
+        let d2 = ledger.try_add_event(ev(c2, author, 5, 6)).unwrap();
+        assert_eq!(ledger.get_tips(), HashSet::from([c1, d2]));
+
+        assert_eq!(ledger.ancestors(&c1), vec![b, a, g]);
+        assert_eq!(ledger.ancestors(&d2), vec![c2, b, a, g]);
+        assert!(ledger.ancestors(&g).is_empty());
+        assert!(ledger.ancestors(&[0xee; 32]).is_empty());
+
+        assert_eq!(ledger.path_to_root(&d2), Some(vec![d2, c2, b, a, g]));
+        assert_eq!(ledger.path_to_root(&g), Some(vec![g]));
+        assert_eq!(ledger.path_to_root(&[0xee; 32]), None);
+
+        assert!(ledger.is_ancestor(&b, &c1));
+        assert!(ledger.is_ancestor(&g, &d2));
+        assert!(
+            !ledger.is_ancestor(&c1, &d2),
+            "siblings' branches are unrelated"
+        );
+        assert!(!ledger.is_ancestor(&c2, &c1));
+        assert!(
+            !ledger.is_ancestor(&d2, &g),
+            "descendant is not an ancestor"
+        );
+        assert!(!ledger.is_ancestor(&c1, &c1), "ancestry is strict");
+        assert!(!ledger.is_ancestor(&ZERO_HASH, &g));
+    }
+
+    #[test]
+    fn link_outcomes_map_to_variants_and_codes() {
+        let author = [8u8; 32];
+        let mut ledger = Ledger::new();
+        ledger.set_single_genesis(true);
+        ledger.set_max_tips(Some(2));
+
+        let g = ledger.try_add_event(ev(ZERO_HASH, author, 1, 1)).unwrap();
+        let a = ledger.try_add_event(ev(g, author, 2, 2)).unwrap();
+        let b = ledger.try_add_event(ev(a, author, 3, 3)).unwrap();
+        let fork = ledger.try_add_event(ev(g, author, 2, 4)).unwrap();
+
+        let cases = [
+            (ev(g, author, 2, 2), LinkError::Duplicate, 1),
+            (ev([0xab; 32], author, 5, 5), LinkError::MissingParent, 2),
+            (ev(a, author, 5, 6), LinkError::TooManyTips { limit: 2 }, 3),
+            (ev(ZERO_HASH, author, 5, 7), LinkError::InvalidGenesis, 4),
+        ];
+        for (event, expected, code) in cases {
+            let err = ledger.try_add_event(event).unwrap_err();
+            assert_eq!(err, expected);
+            assert_eq!(err.code(), code);
+        }
+        // Extending an existing tip is fine at the cap.
+        ledger.try_add_event(ev(fork, author, 3, 8)).unwrap();
+
+        ledger.set_max_tips(None);
+        let c = ledger.try_add_event(ev(b, author, 4, 9)).unwrap();
+        assert_eq!(ledger.prune(&c), Ok(2));
+        let err = ledger.try_add_event(ev(a, author, 5, 10)).unwrap_err();
+        assert_eq!((err, err.code()), (LinkError::Orphaned, 5));
+    }
+
+    #[test]
+    fn incremental_merkle_root_matches_recomputation() {
+        let mut ledger = Ledger::new();
+        let brute = |l: &Ledger| Ledger::merkle_root_of(l.events.keys().copied());
+        assert_eq!(ledger.merkle_root(), None);
+        let mut tips = vec![ZERO_HASH];
+        let mut x = 0x9e37_79b9_7f4a_7c15u64;
+        for i in 0..500u64 {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            let parent = tips[(x % tips.len() as u64) as usize];
+            let mut event = ev(parent, [(x >> 8) as u8; 32], i, (x >> 16) as u8);
+            event.payload_hash[..8].copy_from_slice(&x.to_le_bytes());
+            let h = ledger.try_add_event(event).unwrap();
+            tips.push(h);
+            assert_eq!(ledger.merkle_root(), brute(&ledger), "after insert {}", i);
+        }
+        // The legacy path re-inserting a stored event leaves the leaf set unchanged.
+        let again = ledger.get_event(&tips[1]).unwrap().clone();
+        ledger.add_event(again);
+        assert_eq!(ledger.merkle_root(), brute(&ledger));
+
+        // A separate chain g2 <- c1 <- c2: pruning at c2 drops g2 and c1.
+        let g2 = ledger
+            .try_add_event(ev(ZERO_HASH, [3u8; 32], 1, 0xf1))
+            .unwrap();
+        let c1 = ledger.try_add_event(ev(g2, [3u8; 32], 2, 0xf2)).unwrap();
+        let c2 = ledger.try_add_event(ev(c1, [3u8; 32], 3, 0xf3)).unwrap();
+        assert_eq!(ledger.merkle_root(), brute(&ledger));
+        assert_eq!(ledger.prune(&c2), Ok(2));
+        assert_eq!(ledger.merkle_root(), brute(&ledger));
+        ledger.try_add_event(ev(c2, [3u8; 32], 4, 0xf4)).unwrap();
+        assert_eq!(ledger.merkle_root(), brute(&ledger));
+
+        // Buffered inserts and removals between queries: a chain added and pruned away
+        // before the next root, then re-added on the legacy path.
+        let g3 = ledger
+            .try_add_event(ev(ZERO_HASH, [4u8; 32], 1, 0xe1))
+            .unwrap();
+        let d1 = ledger.try_add_event(ev(g3, [4u8; 32], 2, 0xe2)).unwrap();
+        let d2 = ledger.try_add_event(ev(d1, [4u8; 32], 3, 0xe3)).unwrap();
+        let g3_event = ledger.get_event(&g3).unwrap().clone();
+        assert_eq!(ledger.prune(&d2), Ok(2));
+        ledger.add_event(g3_event.clone());
+        ledger.add_event(g3_event);
+        assert_eq!(ledger.merkle_root(), brute(&ledger));
+        assert_eq!(ledger.clone().merkle_root(), ledger.merkle_root());
+    }
+
+    #[test]
+    fn prune_below_checkpoint() {
+        // g <- a <- b <- cp <- c1
+        //  \<- s
+        let author = [6u8; 32];
+        let mut ledger = Ledger::new();
+        let g = ledger.try_add_event(ev(ZERO_HASH, author, 1, 1)).unwrap();
+        let a = ledger.try_add_event(ev(g, author, 2, 2)).unwrap();
+        let b = ledger.try_add_event(ev(a, author, 3, 3)).unwrap();
+        let cp = ledger.try_add_event(ev(b, author, 4, 4)).unwrap();
+        let c1 = ledger.try_add_event(ev(cp, author, 5, 5)).unwrap();
+        let s = ledger.try_add_event(ev(g, author, 2, 6)).unwrap();
+
+        assert_eq!(
+            ledger.prune(&[0xee; 32]),
+            Err(LedgerError::UnknownCheckpoint)
+        );
+        // g is still reachable from tip s, so only a and b go.
+        assert_eq!(ledger.prune(&cp), Ok(2));
+        assert!(ledger.get_event(&a).is_none() && ledger.get_event(&b).is_none());
+        assert!(ledger.get_event(&g).is_some() && ledger.get_event(&cp).is_some());
+        assert_eq!(ledger.get_tips(), HashSet::from([c1, s]));
+        assert_eq!(ledger.path_to_root(&c1), Some(vec![c1, cp]));
+
+        // Children of the checkpoint (and of its pruned parent, the boundary) link.
+        ledger.try_add_event(ev(cp, author, 6, 7)).unwrap();
+        ledger.try_add_event(ev(b, author, 6, 8)).unwrap();
+        // A child of a deeper removed ancestor is rejected, not silently rooted.
+        assert_eq!(
+            ledger.try_add_event(ev(a, author, 6, 9)),
+            Err(LinkError::Orphaned)
+        );
+
+        // The boundary survives a save/load cycle.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pruned.bin");
+        ledger.save(&path).unwrap();
+        let mut loaded = Ledger::load(&path).unwrap();
+        assert_eq!(loaded.merkle_root(), ledger.merkle_root());
+        loaded.try_add_event(ev(b, author, 7, 10)).unwrap();
+    }
+
+    #[test]
+    fn root_at_height_matches_recorded_checkpoint() {
+        let author = [7u8; 32];
+        let mut ledger = Ledger::new();
+        assert_eq!(ledger.root_at_height(0), None);
+        let mut prev = ZERO_HASH;
+        for i in 0..4u8 {
+            prev = ledger.try_add_event(ev(prev, author, i as u64, i)).unwrap();
+        }
+        // A fork at height 2 also belongs to the height-3 checkpoint.
+        let g = *ledger.path_to_root(&prev).unwrap().last().unwrap();
+        let one = ledger.ancestors(&prev)[1];
+        ledger.try_add_event(ev(one, author, 9, 0x99)).unwrap();
+        let recorded = ledger.merkle_root().unwrap();
+        assert!(ledger.verify_root(recorded));
+
+        for i in 4..10u8 {
+            prev = ledger.try_add_event(ev(prev, author, i as u64, i)).unwrap();
+        }
+        assert!(!ledger.verify_root(recorded));
+        assert_eq!(ledger.root_at_height(3), Some(recorded));
+        assert!(ledger.verify_root(ledger.root_at_height(100).unwrap()));
+
+        let mut genesis_only = Ledger::new();
+        genesis_only
+            .try_add_event(ledger.get_event(&g).unwrap().clone())
+            .unwrap();
+        assert_eq!(ledger.root_at_height(0), genesis_only.merkle_root());
+    }
+
+    #[test]
+    fn difference_of_diverged_ledgers() {
+        let author = [5u8; 32];
+        let mut a = Ledger::new();
+        let mut b = Ledger::new();
+        let mut prev = ZERO_HASH;
+        for i in 0..4u8 {
+            let e = ev(prev, author, i as u64, i);
+            prev = a.try_add_event(e.clone()).unwrap();
+            b.try_add_event(e).unwrap();
+        }
+        assert_eq!(a.difference(&b), (vec![], vec![]));
+
+        let a1 = a.try_add_event(ev(prev, author, 10, 0xa1)).unwrap();
+        let a2 = a.try_add_event(ev(a1, author, 11, 0xa2)).unwrap();
+        let b1 = b.try_add_event(ev(prev, author, 10, 0xb1)).unwrap();
+        assert_ne!(a.merkle_root(), b.merkle_root());
+
+        let mut only_a = vec![a1, a2];
+        only_a.sort();
+        assert_eq!(a.difference(&b), (only_a.clone(), vec![b1]));
+        assert_eq!(b.difference(&a), (vec![b1], only_a));
+    }
+
+    #[test]
+    fn ancestry_walk_is_capped_on_cycles() {
+        // Hand-build a corrupted store: two events pointing at each other.
+        let mut ledger = Ledger::new();
+        let (x, y) = ([0x11; 32], [0x22; 32]);
+        ledger.events.insert(x, ev(y, [1u8; 32], 1, 1));
+        ledger.events.insert(y, ev(x, [1u8; 32], 2, 2));
+        assert_eq!(ledger.path_to_root(&x), None);
+        assert!(ledger.ancestors(&x).is_empty());
+        assert!(!ledger.is_ancestor(&[0x33; 32], &x));
+    }
+
+    #[test]
+    fn save_load_round_trip() {
+        let mut ledger = Ledger::new();
+        let mut hashes = Vec::new();
+        let mut x = 0x2545_f491_4f6c_dd1du64;
+        for i in 0..1000u64 {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            let prev = if hashes.is_empty() || x.is_multiple_of(50) {
+                ZERO_HASH
+            } else {
+                hashes[(x as usize) % hashes.len()]
+            };
+            let author = [(x % 16) as u8; 32];
+            hashes.push(ledger.try_add_event(ev(prev, author, i, i as u8)).unwrap());
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.bin");
+        ledger.save(&path).unwrap();
+        let loaded = Ledger::load(&path).unwrap();
+        assert_eq!(loaded.merkle_root(), ledger.merkle_root());
+        assert_eq!(loaded.get_tips(), ledger.get_tips());
+        assert_eq!(loaded.hashes_set(), ledger.hashes_set());
+
+        // Saving is deterministic.
+        let again = dir.path().join("again.bin");
+        loaded.save(&again).unwrap();
+        assert_eq!(
+            std::fs::read(&path).unwrap(),
+            std::fs::read(&again).unwrap()
+        );
+    }
+
+    #[test]
+    fn load_rejects_tampered_files() {
+        let mut ledger = Ledger::new();
+        let g = ledger
+            .try_add_event(ev(ZERO_HASH, [1u8; 32], 1, 1))
+            .unwrap();
+        ledger.try_add_event(ev(g, [1u8; 32], 2, 2)).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.bin");
+        ledger.save(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        // Nothing pruned: magic, zero boundary and pruned counts, event count.
+        let header = LEDGER_FILE_MAGIC.len() + 24;
+
+        // Child before parent: missing parent on relink.
+        let mut swapped = bytes[..header].to_vec();
+        swapped.extend_from_slice(&bytes[header + WIRE_LEN..]);
+        swapped.extend_from_slice(&bytes[header..header + WIRE_LEN]);
+        std::fs::write(&path, &swapped).unwrap();
+        assert_eq!(
+            Ledger::load(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        // Duplicate record.
+        let mut dup = bytes.clone();
+        dup[header - 8..header].copy_from_slice(&3u64.to_le_bytes());
+        dup.extend_from_slice(&bytes[header..header + WIRE_LEN]);
+        std::fs::write(&path, &dup).unwrap();
+        assert!(Ledger::load(&path).is_err());
+
+        // Truncated record.
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(Ledger::load(&path).is_err());
+    }
+
+    #[test]
+    fn prune_survives_save_and_load() {
+        // g <- a <- b <- cp <- c1
+        let author = [8u8; 32];
+        let mut ledger = Ledger::new();
+        let g = ledger.try_add_event(ev(ZERO_HASH, author, 1, 1)).unwrap();
+        let a = ledger.try_add_event(ev(g, author, 2, 2)).unwrap();
+        let b = ledger.try_add_event(ev(a, author, 3, 3)).unwrap();
+        let cp = ledger.try_add_event(ev(b, author, 4, 4)).unwrap();
+        ledger.try_add_event(ev(cp, author, 5, 5)).unwrap();
+        assert_eq!(ledger.prune(&cp), Ok(3));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.bin");
+        ledger.save(&path).unwrap();
+        let mut loaded = Ledger::load(&path).unwrap();
+        assert_eq!(loaded.merkle_root(), ledger.merkle_root());
+        // Same link results as before the restart.
+        for l in [&mut ledger, &mut loaded] {
+            assert_eq!(
+                l.try_add_event(ev(a, author, 6, 6)),
+                Err(LinkError::Orphaned)
+            );
+            l.try_add_event(ev(b, author, 6, 7)).unwrap();
+        }
+
+        // A boundary that roots no stored event is rejected rather than trusted.
+        let bytes = std::fs::read(&path).unwrap();
+        let at = LEDGER_FILE_MAGIC.len() + 8;
+        assert_eq!(&bytes[at..at + 32], &b);
+        let mut forged = bytes[..at - 8].to_vec();
+        forged.extend_from_slice(&2u64.to_le_bytes());
+        forged.extend_from_slice(&[0xab; 32]);
+        forged.extend_from_slice(&bytes[at..]);
+        std::fs::write(&path, &forged).unwrap();
+        let err = Ledger::load(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(
+            err.to_string().contains("unreferenced prune boundary"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn tip_infos_on_forked_dag() {
+        let (g_author, a_author, b_author) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        let mut ledger = Ledger::new();
+        let g = ledger
+            .try_add_event(ev(ZERO_HASH, g_author, 1, 0x10))
+            .unwrap();
+        let a1 = ledger.try_add_event(ev(g, a_author, 2, 0x11)).unwrap();
+        let a2 = ledger.try_add_event(ev(a1, a_author, 3, 0x12)).unwrap();
+        let b1 = ledger.try_add_event(ev(g, b_author, 2, 0x21)).unwrap();
+        // Conflicting sibling by the same author: B equivocates.
+        let b1x = ledger.try_add_event(ev(g, b_author, 2, 0x22)).unwrap();
+
+        let infos = ledger.tip_infos(None, 0);
+        assert_eq!(infos.len(), 3);
+        let by_hash = |h: Hash| infos.iter().find(|i| i.hash == h).cloned().unwrap();
+        assert_eq!(by_hash(a2).height, 2);
+        assert_eq!(by_hash(a2).cumulative_weight, 3);
+        assert_eq!(by_hash(b1).height, 1);
+        assert_eq!(by_hash(b1x).height, 1);
+
+        let mut overlay = SybilOverlay::new(SybilConfig::default());
+        for h in [g, a1, a2, b1, b1x] {
+            let e = ledger.get_event(&h).unwrap().clone();
+            overlay.observe_event(&ledger, 1, 0, &e, h);
+        }
+        let now = 10;
+        let w_g = overlay.author_weight_fp(now, g_author);
+        let w_a = overlay.author_weight_fp(now, a_author);
+        assert!(w_a > 0);
+        assert_eq!(
+            overlay.author_weight_fp(now, b_author),
+            0,
+            "B is quarantined"
+        );
+
+        let weighted = ledger.tip_infos(Some(&overlay), now);
+        let by_hash = |h: Hash| weighted.iter().find(|i| i.hash == h).cloned().unwrap();
+        assert_eq!(by_hash(a2).cumulative_weight, w_g + 2 * w_a);
+        assert_eq!(by_hash(b1).cumulative_weight, w_g);
+        assert_eq!(by_hash(b1).height, 1);
+    }
+}
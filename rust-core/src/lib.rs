@@ -12,13 +12,13 @@ pub mod bft;
 pub mod validation;
 
 // Global constants for determinism
-pub const PROTOCOL_VERSION: u8 = 1;
+pub const PROTOCOL_VERSION: u8 = 2;
 
 // No randomness or wall clock access; time is injected explicitly.
 
 /*
 Intentionally avoids:
-- async
+- async (except the opt-in `async` storage IO feature)
 - threads
 - global mutable state
 - external IO
@@ -188,12 +188,14 @@ pub fn select_preferred_tip_score(
 }
 
 // ------------------------
-// Sybil overlay: warm-up + equivocation quarantine + economic penalties
+// Sybil overlay: warm-up + equivocation quarantine / slashing
 // ------------------------
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EquivocationPolicy {
+    /// Zero weight for `quarantine_ticks` after each detected fork.
     Quarantine,
+    /// Quarantine, and additionally zero weight forever once an author equivocates.
     Slash,
 }
 
@@ -204,7 +206,6 @@ pub struct SybilConfig {
     pub policy: EquivocationPolicy,
     pub fixed_point_scale: u64,
     pub max_link_walk: usize,
-    pub slash_percent: u64, // Percentage of weight to slash for equivocation (0-100)
 }
 
 impl Default for SybilConfig {
@@ -215,7 +216,6 @@ impl Default for SybilConfig {
             policy: EquivocationPolicy::Quarantine,
             fixed_point_scale: 1000,
             max_link_walk: 4096,
-            slash_percent: 5, // 5% economic penalty
         }
     }
 }
@@ -226,7 +226,9 @@ struct AuthorState {
     seen_by: HashSet<u32>,
     last_tip: Option<Hash>,
     quarantined_until: u64,
-    slashed_weight: u64, // Cumulative slashed amount (fixed-point)
+    /// Slash policy only: height -> event hash of this author's events (one entry per
+    /// distinct height, never trimmed).
+    heights: HashMap<u64, Hash>,
 }
 
 #[derive(Clone, Debug)]
@@ -283,7 +285,7 @@ impl AuthorState {
             seen_by: HashSet::new(),
             last_tip: None,
             quarantined_until: 0,
-            slashed_weight: 0,
+            heights: HashMap::new(),
         }
     }
 }
@@ -292,6 +294,12 @@ impl AuthorState {
 pub struct SybilOverlay {
     pub(crate) cfg: SybilConfig,
     authors: HashMap<PublicKey, AuthorState>,
+    /// Slash policy only: height of every observed event, for duplicate-height detection.
+    /// Never trimmed: one entry (~40 bytes plus map overhead) per observed event, so it
+    /// grows with the ledger; an overlay should not outlive the ledger it observes.
+    event_heights: HashMap<Hash, u64>,
+    /// Authors caught equivocating under the Slash policy; permanently zero weight.
+    slashed: HashSet<PublicKey>,
     trace_recorder: Option<*mut TraceRecorder>,
 }
 
@@ -300,10 +308,17 @@ impl SybilOverlay {
         Self {
             cfg,
             authors: HashMap::new(),
+            event_heights: HashMap::new(),
+            slashed: HashSet::new(),
             trace_recorder: None,
         }
     }
 
+    /// True once `author` has been slashed (Slash policy); never reset.
+    pub fn is_slashed(&self, author: &PublicKey) -> bool {
+        self.slashed.contains(author)
+    }
+
     pub fn attach_trace_recorder(&mut self, recorder: Option<*mut TraceRecorder>) {
         self.trace_recorder = recorder;
     }
@@ -334,14 +349,38 @@ impl SybilOverlay {
         st.seen_by.insert(observer_node);
 
         let mut ancestor_linked = true;
+        let mut equivocated = false;
         if let Some(prev) = st.last_tip && prev != tip {
-            let linked = is_ancestor_by_walk(ledger, prev, tip, self.cfg.max_link_walk)
-                || is_ancestor_by_walk(ledger, tip, prev, self.cfg.max_link_walk);
+            let forward = ancestry_by_walk(ledger, prev, tip, self.cfg.max_link_walk);
+            let backward = ancestry_by_walk(ledger, tip, prev, self.cfg.max_link_walk);
+            let linked = forward == Some(true) || backward == Some(true);
             ancestor_linked = linked;
 
             if !linked {
-                // Fork sibling detected (divergent children of the same author): trigger quarantine + slashing.
-                Self::apply_quarantine_and_slash(st, tick, quarantine_ticks, &self.cfg);
+                // Fork sibling detected (divergent children of the same author): trigger quarantine.
+                Self::apply_quarantine(st, tick, quarantine_ticks);
+                // Only walks that left the stored DAG prove a fork; an exhausted
+                // `max_link_walk` budget may just be a long honest chain (quarantine only).
+                equivocated = forward.is_some() && backward.is_some();
+            }
+        }
+
+        if self.cfg.policy == EquivocationPolicy::Slash {
+            // Heights are derived from observed parents (genesis = 0); a second event by the
+            // same author at a height (e.g. another child of the same parent) is equivocation.
+            let height = if ev.prev_hash == crate::event::ZERO_HASH {
+                Some(0)
+            } else {
+                self.event_heights.get(&ev.prev_hash).map(|h| h + 1)
+            };
+            if let Some(height) = height {
+                self.event_heights.insert(tip, height);
+                if *st.heights.entry(height).or_insert(tip) != tip {
+                    equivocated = true;
+                }
+            }
+            if equivocated {
+                self.slashed.insert(author);
             }
         }
 
@@ -381,46 +420,32 @@ impl SybilOverlay {
             return 0.0;
         };
 
-        if tick < st.quarantined_until {
+        if tick < st.quarantined_until || self.slashed.contains(&author) {
             return 0.0;
         }
 
         let warm = self.author_warmup(tick, st);
-        let base_weight = warm.clamp(0.0, 1.0);
-        
-        // Apply economic penalty from slashing
-        let slashed_fp = st.slashed_weight as f64;
-        let scale = self.cfg.fixed_point_scale as f64;
-        let penalty = (slashed_fp / scale).clamp(0.0, 1.0);
-        
-        // Reduce weight by slashed amount
-        (base_weight * (1.0 - penalty)).clamp(0.0, 1.0)
+        warm.clamp(0.0, 1.0)
     }
 
+    /// Fixed-point weight in `[0, fixed_point_scale]`; the same value `observe_event` records
+    /// in trace rows. 0 for unknown, quarantined, or slashed authors.
     pub fn author_weight_fp(&self, tick: u64, author: PublicKey) -> u64 {
         let w = self.author_weight(tick, author);
         ((w * self.cfg.fixed_point_scale as f64).round() as i64)
             .clamp(0, self.cfg.fixed_point_scale as i64) as u64
     }
 
-    #[allow(dead_code)]
-    fn apply_quarantine(st: &mut AuthorState, tick: u64, quarantine_ticks: u64) {
-        let until = tick.saturating_add(quarantine_ticks);
-        st.quarantined_until = st.quarantined_until.max(until);
+    /// Tick until which `author` is quarantined (0 for unknown or never-quarantined authors).
+    pub fn quarantined_until(&self, author: &PublicKey) -> u64 {
+        self.authors
+            .get(author)
+            .map_or(0, |st| st.quarantined_until)
     }
 
-    fn apply_quarantine_and_slash(st: &mut AuthorState, tick: u64, quarantine_ticks: u64, cfg: &SybilConfig) {
-        // Apply quarantine
+    fn apply_quarantine(st: &mut AuthorState, tick: u64, quarantine_ticks: u64) {
         let until = tick.saturating_add(quarantine_ticks);
         st.quarantined_until = st.quarantined_until.max(until);
-        
-        // Apply economic slash if configured
-        if cfg.policy == EquivocationPolicy::Slash {
-            let slash_amount = (cfg.fixed_point_scale * cfg.slash_percent) / 100;
-            st.slashed_weight = st.slashed_weight.saturating_add(slash_amount);
-            // Cap at fixed_point_scale to prevent overflow and ensure penalty stays at 100%
-            st.slashed_weight = st.slashed_weight.min(cfg.fixed_point_scale);
-        }
     }
 
     fn author_warmup(&self, tick: u64, st: &AuthorState) -> f64 {
@@ -440,31 +465,34 @@ impl SybilOverlay {
     }
 }
 
-fn is_ancestor_by_walk(
+/// Walk up to `max_steps` parents from `descendant`: Some(true) if `ancestor` is reached,
+/// Some(false) if the walk ends first (genesis, missing parent or self-loop), None if the
+/// step budget runs out before either.
+fn ancestry_by_walk(
     ledger: &Ledger,
     ancestor: Hash,
     mut descendant: Hash,
     max_steps: usize,
-) -> bool {
+) -> Option<bool> {
     if ancestor == descendant {
-        return true;
+        return Some(true);
     }
     for _ in 0..max_steps {
         let Some(e) = ledger.get_event(&descendant) else {
-            return false;
+            return Some(false);
         };
         if e.prev_hash == ancestor {
-            return true;
+            return Some(true);
         }
         if e.prev_hash == descendant {
-            return false;
+            return Some(false);
         }
         descendant = e.prev_hash;
         if descendant == ancestor {
-            return true;
+            return Some(true);
         }
     }
-    false
+    None
 }
 
 pub fn score_chain_latest_per_author_sybil_fp(
@@ -534,6 +562,120 @@ mod tests {
         let _ = LocalPolicyKind::LatestPerAuthorUnit;
     }
 
+    #[test]
+    fn weight_getters_follow_warmup_and_quarantine() {
+        let cfg = SybilConfig::default();
+        let scale = cfg.fixed_point_scale;
+        let warmup = cfg.warmup_ticks;
+        let mut ledger = Ledger::new();
+        let mut overlay = SybilOverlay::new(cfg);
+        let author: PublicKey = [0x44; 32];
+        assert_eq!(overlay.author_weight_fp(5, author), 0);
+        assert_eq!(overlay.quarantined_until(&author), 0);
+
+        let e = Event::new(ZERO_HASH, author, 1, [0x01; 32], [0u8; 64]);
+        let h = ledger.add_event(e.clone());
+        // Full weight needs both age >= warmup_ticks and enough distinct observers (8).
+        for node in 0..8 {
+            overlay.observe_event(&ledger, 10, node, &e, h);
+        }
+        assert_eq!(overlay.quarantined_until(&author), 0);
+        let mut last = 0;
+        for t in 10..10 + warmup {
+            let w = overlay.author_weight_fp(t, author);
+            assert!(w >= last && w < scale, "still warming up at {}: {}", t, w);
+            last = w;
+        }
+        assert_eq!(overlay.author_weight_fp(10 + warmup, author), scale);
+
+        // A fork quarantines: zero until quarantined_until, then back to full weight.
+        let fork = Event::new(ZERO_HASH, author, 2, [0x02; 32], [0u8; 64]);
+        let hf = ledger.add_event(fork.clone());
+        overlay.observe_event(&ledger, 30, 0, &fork, hf);
+        let until = overlay.quarantined_until(&author);
+        assert_eq!(until, 30 + overlay.config().quarantine_ticks);
+        assert_eq!(overlay.author_weight_fp(until - 1, author), 0);
+        assert_eq!(overlay.author_weight_fp(until, author), scale);
+    }
+
+    #[test]
+    fn slash_policy_zeroes_weight_permanently() {
+        let secret: [u8; 32] = [0x43; 32];
+        let secret_key = ed25519_dalek::SecretKey::from_bytes(&secret).unwrap();
+        let author: PublicKey = ed25519_dalek::PublicKey::from(&secret_key).to_bytes();
+        let signed = |prev, ts, tag| {
+            let mut e = Event::new(prev, author, ts, [tag; 32], [0u8; 64]);
+            e.signature = crate::validation::compute_signature_with_secret(&e, &secret);
+            e
+        };
+
+        for policy in [EquivocationPolicy::Quarantine, EquivocationPolicy::Slash] {
+            let cfg = SybilConfig {
+                policy,
+                ..SybilConfig::default()
+            };
+            let late = 10 + cfg.quarantine_ticks + cfg.warmup_ticks + 100;
+            let mut ledger = Ledger::new();
+            let mut overlay = SybilOverlay::new(cfg);
+            let g = ledger.try_add_event(signed(ZERO_HASH, 1, 0x10)).unwrap();
+            overlay.observe_event(&ledger, 1, 0, ledger.get_event(&g).unwrap(), g);
+            // Two conflicting children of the same parent.
+            for (ts, tag) in [(2, 0x11), (3, 0x12)] {
+                let e = signed(g, ts, tag);
+                let h = ledger.try_add_event(e.clone()).unwrap();
+                overlay.observe_event(&ledger, 10, 0, &e, h);
+            }
+            assert_eq!(overlay.author_weight_fp(11, author), 0);
+
+            match policy {
+                EquivocationPolicy::Quarantine => {
+                    assert!(!overlay.is_slashed(&author));
+                    assert!(
+                        overlay.author_weight_fp(late, author) > 0,
+                        "quarantine expires"
+                    );
+                }
+                EquivocationPolicy::Slash => {
+                    assert!(overlay.is_slashed(&author));
+                    assert_eq!(
+                        overlay.author_weight_fp(late, author),
+                        0,
+                        "slash is permanent"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn slash_ignores_exhausted_link_walk() {
+        // The author's next event sits 6 parents above its previous one, beyond a walk
+        // budget of 4: that is quarantined as before, but not proof of equivocation.
+        let author: PublicKey = [0x45; 32];
+        let other: PublicKey = [0x46; 32];
+        let cfg = SybilConfig {
+            policy: EquivocationPolicy::Slash,
+            max_link_walk: 4,
+            ..SybilConfig::default()
+        };
+        let mut ledger = Ledger::new();
+        let mut overlay = SybilOverlay::new(cfg);
+        let g = Event::new(ZERO_HASH, author, 1, [0x20; 32], [0u8; 64]);
+        let mut parent = ledger.try_add_event(g.clone()).unwrap();
+        overlay.observe_event(&ledger, 1, 0, &g, parent);
+        for i in 0..5u8 {
+            let e = Event::new(parent, other, 2 + i as u64, [0x30 + i; 32], [0u8; 64]);
+            parent = ledger.try_add_event(e.clone()).unwrap();
+            overlay.observe_event(&ledger, 2, 0, &e, parent);
+        }
+        let next = Event::new(parent, author, 10, [0x21; 32], [0u8; 64]);
+        let h = ledger.try_add_event(next.clone()).unwrap();
+        overlay.observe_event(&ledger, 10, 0, &next, h);
+
+        assert!(overlay.quarantined_until(&author) > 10);
+        assert!(!overlay.is_slashed(&author));
+    }
+
     #[test]
     fn sybil_overlay_detects_equivocation_and_zeroes_weight() {
         let mut ledger = Ledger::new();
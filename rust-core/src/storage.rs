@@ -2,11 +2,23 @@ use crate::event::{Hash, PublicKey, Signature};
 use ed25519_dalek::Signature as DalekSignature;
 use ed25519_dalek::{ExpandedSecretKey, PublicKey as DalekPublicKey, SecretKey};
 use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 
+#[cfg(feature = "async")]
+pub mod asynchronous;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "parity")]
+mod parity;
+#[cfg(feature = "parity")]
+pub use parity::{add_parity, chunk_file_to_dir_with_parity, reassemble_with_repair};
+
 const MANIFEST_MAGIC: &str = "dvel-manifest-v1";
+/// v2 manifests carry an explicit length per chunk (variable-size / content-defined chunks).
+const MANIFEST_MAGIC_V2: &str = "dvel-manifest-v2";
 
 #[derive(Debug)]
 pub enum StorageError {
@@ -14,7 +26,45 @@ pub enum StorageError {
     InvalidManifest(String),
     SignatureMissing,
     SignatureInvalid,
-    HashMismatch { index: usize },
+    HashMismatch {
+        index: usize,
+    },
+    SizeOverflow,
+    ThresholdNotMet {
+        valid: usize,
+        required: usize,
+    },
+    /// Malformed line in a `SHA256SUMS`-style checksum file (1-based line number).
+    InvalidSums {
+        line: usize,
+        reason: String,
+    },
+    /// A file listed in a checksum file does not hash to its listed digest.
+    ChecksumMismatch {
+        file: String,
+    },
+    /// An encrypted chunk failed authentication (wrong key or tampered ciphertext).
+    DecryptFailed {
+        index: usize,
+    },
+    /// The manifest declares encrypted chunks but no key was supplied.
+    KeyRequired,
+    /// Chunk `index` of a streamed upload differs from the manifest (hash, or a short or
+    /// overlong stream); `consumed` bytes had been read when it was detected.
+    StreamMismatch {
+        index: usize,
+        consumed: u64,
+    },
+    /// `verify_chunks_strict` found a chunk file for this manifest's name at an index the
+    /// manifest does not list.
+    UnexpectedChunk {
+        index: usize,
+    },
+    /// More data chunks are missing or corrupt than the manifest's parity can rebuild.
+    Unrecoverable {
+        lost: usize,
+        parity: usize,
+    },
 }
 
 impl From<std::io::Error> for StorageError {
@@ -33,6 +83,29 @@ impl std::fmt::Display for StorageError {
             StorageError::HashMismatch { index } => {
                 write!(f, "chunk {} hash mismatch", index)
             }
+            StorageError::SizeOverflow => write!(f, "total size overflows u64"),
+            StorageError::ThresholdNotMet { valid, required } => {
+                write!(f, "{} valid signatures, {} required", valid, required)
+            }
+            StorageError::InvalidSums { line, reason } => {
+                write!(f, "checksum file line {}: {}", line, reason)
+            }
+            StorageError::ChecksumMismatch { file } => write!(f, "{} checksum mismatch", file),
+            StorageError::DecryptFailed { index } => write!(f, "chunk {} decryption failed", index),
+            StorageError::KeyRequired => write!(f, "chunks are encrypted; a key is required"),
+            StorageError::StreamMismatch { index, consumed } => write!(
+                f,
+                "stream chunk {} does not match manifest ({} bytes consumed)",
+                index, consumed
+            ),
+            StorageError::UnexpectedChunk { index } => {
+                write!(f, "unexpected chunk file at index {}", index)
+            }
+            StorageError::Unrecoverable { lost, parity } => write!(
+                f,
+                "{} chunks lost, parity can rebuild at most {}",
+                lost, parity
+            ),
         }
     }
 }
@@ -42,8 +115,41 @@ impl std::error::Error for StorageError {}
 #[derive(Debug, Clone)]
 pub struct ChunkMeta {
     pub hash: [u8; 32],
+    /// Chunk length in bytes (serialized only in v2 manifests; derived from chunk_size in v1).
+    pub len: u64,
+}
+
+/// At-rest cipher for chunk files. `ChunkMeta.hash` always covers the plaintext.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkEncryption {
+    /// XChaCha20-Poly1305; nonce derived from the chunk index and plaintext hash.
+    XChaCha20Poly1305,
+}
+
+impl ChunkEncryption {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChunkEncryption::XChaCha20Poly1305 => "xchacha20poly1305",
+        }
+    }
+}
+
+/// Reed–Solomon (GF(2^8)) parity over the data chunks, each zero-padded to the longest
+/// chunk. Serialized as `parity:<k>:<m>` followed by `m` `p:<hash>` lines, so it is signed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParityInfo {
+    /// Number of data shards `k`; always `chunks.len()`.
+    pub data_shards: usize,
+    /// `chunk_hash` of each parity shard; `hashes.len()` is the parity count `m`.
+    pub hashes: Vec<Hash>,
 }
 
+/// GF(2^8) Reed–Solomon caps data plus parity shards at 256.
+pub const MAX_PARITY_SHARDS: usize = 256;
+
+/// Largest `max` accepted by `chunk_file_cdc` (64 MiB); it buffers about `2 * max` bytes.
+pub const MAX_CDC_CHUNK: usize = 64 * 1024 * 1024;
+
 #[derive(Debug, Clone)]
 pub struct Manifest {
     pub version: u8,
@@ -51,27 +157,102 @@ pub struct Manifest {
     pub total_size: u64,
     pub chunk_size: u64,
     pub chunks: Vec<ChunkMeta>,
-    pub signer: Option<PublicKey>,
-    pub signature: Option<Signature>,
+    /// Chunk file naming: None keeps `<file>.chunk.NNNNNNNN`; Some(sfx) names chunks
+    /// `<file>.NNNNNNNN<sfx>` (sfx may be empty, e.g. ".dat" or "").
+    pub chunk_suffix: Option<String>,
+    /// Set when chunk files are encrypted at rest (`encryption:<cipher>` line, signed).
+    pub encryption: Option<ChunkEncryption>,
+    /// Serialize each run of identical consecutive chunks as one `r:<count>:<hash>[:<len>]`
+    /// line instead of `count` `h:` lines (set by `with_chunk_runs` or when parsing `r:`).
+    /// `chunks` always holds the expanded list.
+    pub chunk_runs: bool,
+    /// Free-form signed tags (creation time, MIME type, ...) as `meta:<key>=<value>` lines,
+    /// sorted by key. Keys and values may not contain `=`, CR or LF; see `set_metadata`.
+    pub metadata: BTreeMap<String, String>,
+    /// Parity chunks for `reassemble_with_repair` (feature `parity`); None if there are none.
+    pub parity: Option<ParityInfo>,
+    /// (signer, signature) pairs, each over the unsigned canonical bytes.
+    pub signers: Vec<(PublicKey, Signature)>,
 }
 
 impl Manifest {
     fn canonical_string(&self) -> String {
         let mut out = String::new();
-        out.push_str(MANIFEST_MAGIC);
+        if self.version >= 2 {
+            out.push_str(MANIFEST_MAGIC_V2);
+        } else {
+            out.push_str(MANIFEST_MAGIC);
+        }
         out.push('\n');
         out.push_str(&format!("file_name:{}\n", self.file_name));
         out.push_str(&format!("total_size:{}\n", self.total_size));
         out.push_str(&format!("chunk_size:{}\n", self.chunk_size));
+        if let Some(sfx) = &self.chunk_suffix {
+            out.push_str(&format!("chunk_suffix:{}\n", sfx));
+        }
+        if let Some(enc) = self.encryption {
+            out.push_str(&format!("encryption:{}\n", enc.as_str()));
+        }
+        for (k, v) in &self.metadata {
+            out.push_str(&format!("meta:{}={}\n", k, v));
+        }
         out.push_str(&format!("chunks:{}\n", self.chunks.len()));
-        for c in &self.chunks {
-            out.push_str("h:");
+        // Runs are maximal and only used for count >= 2, so the encoding is unique.
+        let mut i = 0;
+        while i < self.chunks.len() {
+            let c = &self.chunks[i];
+            let mut run = 1;
+            if self.chunk_runs {
+                while i + run < self.chunks.len()
+                    && self.chunks[i + run].hash == c.hash
+                    && self.chunks[i + run].len == c.len
+                {
+                    run += 1;
+                }
+            }
+            if run >= 2 {
+                out.push_str(&format!("r:{}:", run));
+            } else {
+                out.push_str("h:");
+            }
             out.push_str(&hex::encode(c.hash));
+            if self.version >= 2 {
+                out.push_str(&format!(":{}", c.len));
+            }
             out.push('\n');
+            i += run;
+        }
+        if let Some(parity) = &self.parity {
+            out.push_str(&format!(
+                "parity:{}:{}\n",
+                parity.data_shards,
+                parity.hashes.len()
+            ));
+            for h in &parity.hashes {
+                out.push_str("p:");
+                out.push_str(&hex::encode(h));
+                out.push('\n');
+            }
         }
         out
     }
 
+    /// Insert a metadata entry after checking it fits the line format. Metadata is part of
+    /// the canonical bytes, so set it before signing.
+    pub fn set_metadata(&mut self, key: &str, value: &str) -> Result<(), StorageError> {
+        check_metadata_entry(key, value)?;
+        self.metadata.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    /// Builder form of setting `chunk_runs`: repeated chunks (e.g. zero-filled regions of
+    /// sparse files or VM images) serialize as run lines. Changes the canonical bytes, so
+    /// set it before signing.
+    pub fn with_chunk_runs(mut self) -> Self {
+        self.chunk_runs = true;
+        self
+    }
+
     pub fn canonical_bytes(&self) -> Vec<u8> {
         self.canonical_string().into_bytes()
     }
@@ -86,24 +267,94 @@ impl Manifest {
         merkle_root(&self.chunks.iter().map(|c| c.hash).collect::<Vec<_>>())
     }
 
+    /// `merkle_root_v2` over chunk hashes (domain-separated, no odd-node duplication).
+    pub fn chunk_merkle_root_v2(&self) -> Option<Hash> {
+        merkle_root_v2(&self.chunks.iter().map(|c| c.hash).collect::<Vec<_>>())
+    }
+
+    /// Path of chunk `index` under `dir`, honouring `chunk_suffix`.
+    pub fn chunk_path(&self, dir: &Path, index: usize) -> PathBuf {
+        chunk_path_with_suffix(dir, &self.file_name, self.chunk_suffix.as_deref(), index)
+    }
+
+    /// Path of parity chunk `index` under `dir` (see `parity_path`).
+    pub fn parity_path(&self, dir: &Path, index: usize) -> PathBuf {
+        parity_path(dir, &self.file_name, index)
+    }
+
+    /// True if `key` is among the manifest's signers (signature validity is checked separately).
+    pub fn has_signer(&self, key: &PublicKey) -> bool {
+        self.signers.iter().any(|(s, _)| s == key)
+    }
+
     pub fn to_string_with_signature(&self) -> String {
         let mut out = self.canonical_string();
-        if let Some(signer) = &self.signer {
+        for (signer, sig) in &self.signers {
             out.push_str("signer:");
             out.push_str(&hex::encode(signer));
             out.push('\n');
-        }
-        if let Some(sig) = &self.signature {
             out.push_str("signature:");
             out.push_str(&hex::encode(sig));
             out.push('\n');
         }
         out
     }
+
+    /// Byte-exact serialization for reproducible publishing: the canonical body followed by
+    /// the signer pairs sorted by public key, so the output depends only on the manifest's
+    /// content and not on the order signatures were added. Manifests record no timestamps
+    /// or other environment data, so chunking identical input twice yields identical bytes.
+    pub fn to_reproducible_string(&self) -> String {
+        let mut sorted = self.clone();
+        sorted.signers.sort();
+        sorted.to_string_with_signature()
+    }
+}
+
+fn chunk_filename(file_name: &str, suffix: Option<&str>, index: usize) -> String {
+    match suffix {
+        None => format!("{}.chunk.{:08}", file_name, index),
+        Some(sfx) => format!("{}.{:08}{}", file_name, index, sfx),
+    }
+}
+
+/// Inverse of `chunk_filename`: the index if `name` is exactly the chunk file name for
+/// some index (so `x.chunk.000000099`, with a non-canonical width, does not count).
+fn chunk_index_from_filename(name: &str, file_name: &str, suffix: Option<&str>) -> Option<usize> {
+    let rest = name.strip_prefix(file_name)?.strip_prefix('.')?;
+    let digits = match suffix {
+        None => rest.strip_prefix("chunk.")?,
+        Some(sfx) => rest.strip_suffix(sfx)?,
+    };
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let index = digits.parse::<usize>().ok()?;
+    (chunk_filename(file_name, suffix, index) == name).then_some(index)
+}
+
+/// A suffix must stay inside the chunk directory and on one manifest line, and may not end
+/// in whitespace (the parser trims line ends, so it would not read back).
+fn check_chunk_suffix(sfx: &str) -> Result<(), StorageError> {
+    if sfx.contains(['/', '\\', '\n', '\r']) || sfx.trim_end() != sfx {
+        return Err(StorageError::InvalidManifest("invalid chunk suffix".into()));
+    }
+    Ok(())
 }
 
-fn chunk_filename(file_name: &str, index: usize) -> String {
-    format!("{}.chunk.{:08}", file_name, index)
+/// Keep `meta:<key>=<value>` unambiguous and round-trippable: non-empty key, no `=` or line
+/// breaks in either part, and no trailing whitespace (the parser trims line ends).
+fn check_metadata_entry(key: &str, value: &str) -> Result<(), StorageError> {
+    let bad = |s: &str| s.contains(['=', '\n', '\r']) || s.trim_end() != s;
+    if key.is_empty() || bad(key) {
+        return Err(StorageError::InvalidManifest("invalid metadata key".into()));
+    }
+    if bad(value) {
+        return Err(StorageError::InvalidManifest(
+            "invalid metadata value".into(),
+        ));
+    }
+    Ok(())
 }
 
 pub fn manifest_path(dir: &Path, file_name: &str) -> PathBuf {
@@ -111,7 +362,89 @@ pub fn manifest_path(dir: &Path, file_name: &str) -> PathBuf {
 }
 
 pub fn chunk_path(dir: &Path, file_name: &str, index: usize) -> PathBuf {
-    dir.join(chunk_filename(file_name, index))
+    chunk_path_with_suffix(dir, file_name, None, index)
+}
+
+/// Parity chunk files are `<file>.parity.NNNNNNNN` whatever the data chunk suffix.
+pub fn parity_path(dir: &Path, file_name: &str, index: usize) -> PathBuf {
+    dir.join(format!("{}.parity.{:08}", file_name, index))
+}
+
+pub fn chunk_path_with_suffix(
+    dir: &Path,
+    file_name: &str,
+    suffix: Option<&str>,
+    index: usize,
+) -> PathBuf {
+    dir.join(chunk_filename(file_name, suffix, index))
+}
+
+/// Chunk hash for a manifest version. v1: `SHA256(data)`. v2 and later bind the length too,
+/// `SHA256(len_u64_le || data)`, so a short chunk can never pass as a prefix of a longer one.
+pub fn chunk_hash(version: u8, data: &[u8]) -> Hash {
+    if version < 2 {
+        return sha256_bytes(data);
+    }
+    let mut h = Sha256::new();
+    h.update((data.len() as u64).to_le_bytes());
+    h.update(data);
+    h.finalize().into()
+}
+
+/// Per-chunk XChaCha20-Poly1305 nonce: SHA256("dvel-chunk-nonce" || index u64 LE ||
+/// plaintext hash), first 24 bytes. A nonce repeats only for the same plaintext at the same
+/// index, and swapping chunk files fails authentication.
+#[cfg(feature = "encryption")]
+fn chunk_nonce(idx: usize, hash: &Hash) -> [u8; 24] {
+    let mut h = Sha256::new();
+    h.update(b"dvel-chunk-nonce");
+    h.update((idx as u64).to_le_bytes());
+    h.update(hash);
+    let mut out = [0u8; 24];
+    out.copy_from_slice(&h.finalize()[..24]);
+    out
+}
+
+#[cfg(feature = "encryption")]
+fn seal_chunk(key: &[u8; 32], idx: usize, hash: &Hash, data: &[u8]) -> Vec<u8> {
+    use chacha20poly1305::aead::{Aead, KeyInit};
+    use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+    XChaCha20Poly1305::new(key.into())
+        .encrypt(XNonce::from_slice(&chunk_nonce(idx, hash)), data)
+        .expect("xchacha20poly1305 encryption is infallible for in-memory buffers")
+}
+
+#[cfg(feature = "encryption")]
+fn open_sealed_chunk(
+    key: &[u8; 32],
+    idx: usize,
+    hash: &Hash,
+    data: &[u8],
+) -> Result<Vec<u8>, StorageError> {
+    use chacha20poly1305::aead::{Aead, KeyInit};
+    use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(&chunk_nonce(idx, hash)), data)
+        .map_err(|_| StorageError::DecryptFailed { index: idx })
+}
+
+/// Plaintext of chunk `idx` from its on-disk bytes: unchanged for unencrypted manifests,
+/// otherwise decrypted under `key` (`KeyRequired` if none was given).
+#[cfg_attr(not(feature = "encryption"), allow(unused_variables))]
+fn open_chunk(
+    manifest: &Manifest,
+    idx: usize,
+    key: Option<&[u8; 32]>,
+    raw: Vec<u8>,
+) -> Result<Vec<u8>, StorageError> {
+    match (manifest.encryption, key) {
+        (None, _) => Ok(raw),
+        #[cfg(feature = "encryption")]
+        (Some(ChunkEncryption::XChaCha20Poly1305), Some(key)) => {
+            open_sealed_chunk(key, idx, &manifest.chunks[idx].hash, &raw)
+        }
+        _ => Err(StorageError::KeyRequired),
+    }
 }
 
 fn sha256_bytes(data: &[u8]) -> [u8; 32] {
@@ -123,6 +456,47 @@ fn sha256_bytes(data: &[u8]) -> [u8; 32] {
     out
 }
 
+/// Leaf and internal-node prefixes for `merkle_root_v2`.
+const MERKLE_LEAF_TAG: u8 = 0x00;
+const MERKLE_NODE_TAG: u8 = 0x01;
+
+/// Second-preimage-resistant Merkle root over sorted leaves: leaves hash as
+/// `SHA256(0x00 || leaf)`, internal nodes as `SHA256(0x01 || left || right)`, and an
+/// unpaired node on an odd level is promoted unchanged rather than paired with itself.
+/// An internal node can therefore never be passed off as a leaf, and `[a, b, c]` no longer
+/// shares a root with `[a, b, c, c]`. Differs from the v1 roots (`chunk_merkle_root`,
+/// `Ledger::merkle_root`), which stay as they are so existing commitments still reproduce.
+pub fn merkle_root_v2(leaves: &[Hash]) -> Option<Hash> {
+    let mut sorted = leaves.to_vec();
+    sorted.sort();
+    let mut level: Vec<Hash> = sorted
+        .iter()
+        .map(|leaf| {
+            let mut hasher = Sha256::new();
+            hasher.update([MERKLE_LEAF_TAG]);
+            hasher.update(leaf);
+            hasher.finalize().into()
+        })
+        .collect();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => {
+                    let mut hasher = Sha256::new();
+                    hasher.update([MERKLE_NODE_TAG]);
+                    hasher.update(a);
+                    hasher.update(b);
+                    hasher.finalize().into()
+                }
+                [odd] => *odd,
+                _ => unreachable!("chunks(2) yields one or two nodes"),
+            })
+            .collect();
+    }
+    level.first().copied()
+}
+
 fn merkle_root(leaves: &[Hash]) -> Option<Hash> {
     if leaves.is_empty() {
         return None;
@@ -170,6 +544,163 @@ pub fn chunk_file_to_dir(
     out_dir: &Path,
     chunk_size: usize,
 ) -> Result<Manifest, StorageError> {
+    chunk_file_to_dir_with_suffix(input, out_dir, chunk_size, None)
+}
+
+/// Like `chunk_file_to_dir`, naming chunk files with `suffix` (see `Manifest::chunk_suffix`).
+pub fn chunk_file_to_dir_with_suffix(
+    input: &Path,
+    out_dir: &Path,
+    chunk_size: usize,
+    suffix: Option<&str>,
+) -> Result<Manifest, StorageError> {
+    chunk_fixed(
+        input,
+        out_dir,
+        chunk_size,
+        suffix,
+        None,
+        &IoOptions::default(),
+        None,
+    )
+}
+
+/// `chunk_file_to_dir` that also writes a verbatim copy of `input` to `copy_to` from the
+/// same single read pass (archive + working copy without reading a large file twice).
+/// `copy_to` must not be `input` itself.
+pub fn chunk_file_tee(
+    input: &Path,
+    out_dir: &Path,
+    copy_to: &Path,
+    chunk_size: usize,
+) -> Result<Manifest, StorageError> {
+    if copy_to.exists() && fs::canonicalize(copy_to)? == fs::canonicalize(input)? {
+        return Err(StorageError::InvalidManifest(
+            "copy_to must differ from input".into(),
+        ));
+    }
+    chunk_fixed(
+        input,
+        out_dir,
+        chunk_size,
+        None,
+        None,
+        &IoOptions::default(),
+        Some(copy_to),
+    )
+}
+
+/// `Read` adapter that writes every byte it yields to `copy`.
+struct TeeReader<R, W> {
+    inner: R,
+    copy: W,
+}
+
+impl<R: Read, W: Write> Read for TeeReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.copy.write_all(&buf[..n])?;
+        Ok(n)
+    }
+}
+
+/// Resource knobs for large chunking / verification runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoOptions {
+    /// Worker threads for hashing and chunk IO (`parallel` feature only; ignored otherwise).
+    /// Default: available parallelism.
+    pub jobs: usize,
+    /// Chunks read ahead per parallel batch; bounds memory to ~readahead * chunk_size
+    /// (`parallel` feature only). Default 64.
+    pub readahead: usize,
+    /// Capacity of the buffered reader over the input file, in bytes. Default 64 KiB.
+    pub io_buffer: usize,
+}
+
+impl Default for IoOptions {
+    fn default() -> Self {
+        IoOptions {
+            jobs: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            readahead: 64,
+            io_buffer: 64 * 1024,
+        }
+    }
+}
+
+impl IoOptions {
+    fn check(&self) -> Result<(), StorageError> {
+        if self.jobs == 0 || self.readahead == 0 || self.io_buffer == 0 {
+            return Err(StorageError::InvalidManifest(
+                "jobs, readahead and io_buffer must be > 0".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// `chunk_file_to_dir_with_suffix` with explicit `IoOptions`. Output is identical for any
+/// valid options.
+pub fn chunk_file_to_dir_with_options(
+    input: &Path,
+    out_dir: &Path,
+    chunk_size: usize,
+    suffix: Option<&str>,
+    opts: &IoOptions,
+) -> Result<Manifest, StorageError> {
+    chunk_fixed(input, out_dir, chunk_size, suffix, None, opts, None)
+}
+
+/// Run `f` on a rayon pool of `jobs` threads; the global pool is reused when it already
+/// has that many.
+#[cfg(feature = "parallel")]
+fn with_jobs<T: Send>(
+    jobs: usize,
+    f: impl FnOnce() -> Result<T, StorageError> + Send,
+) -> Result<T, StorageError> {
+    if jobs == rayon::current_num_threads() {
+        return f();
+    }
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .map_err(|e| StorageError::Io(std::io::Error::other(e)))?
+        .install(f)
+}
+
+/// `chunk_file_to_dir` with every chunk file sealed under `key` (XChaCha20-Poly1305). The
+/// manifest still records plaintext hashes and gains a signed `encryption:` line; read the
+/// chunks back with `verify_chunks_with_key` / `reassemble_with_key`.
+#[cfg(feature = "encryption")]
+pub fn chunk_file_to_dir_encrypted(
+    input: &Path,
+    out_dir: &Path,
+    chunk_size: usize,
+    key: &[u8; 32],
+) -> Result<Manifest, StorageError> {
+    chunk_fixed(
+        input,
+        out_dir,
+        chunk_size,
+        None,
+        Some(key),
+        &IoOptions::default(),
+        None,
+    )
+}
+
+fn chunk_fixed(
+    input: &Path,
+    out_dir: &Path,
+    chunk_size: usize,
+    suffix: Option<&str>,
+    key: Option<&[u8; 32]>,
+    opts: &IoOptions,
+    copy_to: Option<&Path>,
+) -> Result<Manifest, StorageError> {
+    if let Some(sfx) = suffix {
+        check_chunk_suffix(sfx)?;
+    }
+    opts.check()?;
     if chunk_size == 0 {
         return Err(StorageError::InvalidManifest(
             "chunk_size must be > 0".into(),
@@ -182,35 +713,298 @@ pub fn chunk_file_to_dir(
         .ok_or_else(|| StorageError::InvalidManifest("invalid file name".into()))?
         .to_string();
 
-    let mut f = File::open(input)?;
+    let copy: Box<dyn Write + Send> = match copy_to {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(std::io::sink()),
+    };
+    let mut f = TeeReader {
+        inner: BufReader::with_capacity(opts.io_buffer, File::open(input)?),
+        copy,
+    };
+    #[cfg(feature = "parallel")]
+    let (chunks, total) = with_jobs(opts.jobs, || {
+        chunk_stream_parallel(
+            &mut f,
+            out_dir,
+            &file_name,
+            suffix,
+            chunk_size,
+            key,
+            opts.readahead,
+        )
+    })?;
+    #[cfg(not(feature = "parallel"))]
+    let (chunks, total) =
+        chunk_stream_sequential(&mut f, out_dir, &file_name, suffix, chunk_size, key)?;
+    f.copy.flush()?;
+
+    Ok(Manifest {
+        version: 1,
+        file_name,
+        total_size: total,
+        chunk_size: chunk_size as u64,
+        chunks,
+        chunk_suffix: suffix.map(str::to_string),
+        encryption: key.map(|_| ChunkEncryption::XChaCha20Poly1305),
+        chunk_runs: false,
+        metadata: BTreeMap::new(),
+        parity: None,
+        signers: Vec::new(),
+    })
+}
+
+/// Size accumulation must fail loudly: a capped total could mask a size mismatch.
+fn add_size(total: u64, n: u64) -> Result<u64, StorageError> {
+    total.checked_add(n).ok_or(StorageError::SizeOverflow)
+}
+
+/// Sum of the per-chunk lengths; `SizeOverflow` if they wrap.
+fn declared_size(chunks: &[ChunkMeta]) -> Result<u64, StorageError> {
+    chunks.iter().try_fold(0u64, |acc, c| add_size(acc, c.len))
+}
+
+/// Fill `buf` as far as the reader allows; short only at EOF. Keeps chunk boundaries
+/// independent of how the underlying reader splits its reads.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn write_chunk(
+    out_dir: &Path,
+    file_name: &str,
+    suffix: Option<&str>,
+    version: u8,
+    idx: usize,
+    data: &[u8],
+    key: Option<&[u8; 32]>,
+) -> Result<ChunkMeta, StorageError> {
+    let hash = chunk_hash(version, data);
+    let mut out = File::create(chunk_path_with_suffix(out_dir, file_name, suffix, idx))?;
+    match key {
+        #[cfg(feature = "encryption")]
+        Some(key) => out.write_all(&seal_chunk(key, idx, &hash, data))?,
+        _ => out.write_all(data)?,
+    }
+    Ok(ChunkMeta {
+        hash,
+        len: data.len() as u64,
+    })
+}
+
+/// Fixed-size chunking loop: hash + write one chunk at a time.
+#[cfg_attr(feature = "parallel", allow(dead_code))]
+fn chunk_stream_sequential<R: Read>(
+    reader: &mut R,
+    out_dir: &Path,
+    file_name: &str,
+    suffix: Option<&str>,
+    chunk_size: usize,
+    key: Option<&[u8; 32]>,
+) -> Result<(Vec<ChunkMeta>, u64), StorageError> {
     let mut buf = vec![0u8; chunk_size];
     let mut chunks = Vec::new();
-    let mut idx: usize = 0;
     let mut total: u64 = 0;
 
     loop {
-        let n = f.read(&mut buf)?;
+        let n = read_full(reader, &mut buf)?;
         if n == 0 {
             break;
         }
-        let data = &buf[..n];
-        total = total.saturating_add(n as u64);
-        let hash = sha256_bytes(data);
-        let chunk_path = chunk_path(out_dir, &file_name, idx);
-        let mut out = File::create(chunk_path)?;
-        out.write_all(data)?;
-        chunks.push(ChunkMeta { hash });
-        idx += 1;
+        total = add_size(total, n as u64)?;
+        chunks.push(write_chunk(
+            out_dir,
+            file_name,
+            suffix,
+            1,
+            chunks.len(),
+            &buf[..n],
+            key,
+        )?);
+    }
+    Ok((chunks, total))
+}
+
+/// Fixed-size chunking with hashing/writes spread over rayon. Reading stays sequential and
+/// results are collected in input order, so index i is always the i-th chunk of the file.
+/// Up to `batch_chunks` chunks are buffered per batch (memory ~batch_chunks * chunk_size).
+#[cfg(feature = "parallel")]
+fn chunk_stream_parallel<R: Read>(
+    reader: &mut R,
+    out_dir: &Path,
+    file_name: &str,
+    suffix: Option<&str>,
+    chunk_size: usize,
+    key: Option<&[u8; 32]>,
+    batch_chunks: usize,
+) -> Result<(Vec<ChunkMeta>, u64), StorageError> {
+    use rayon::prelude::*;
+
+    let mut chunks = Vec::new();
+    let mut total: u64 = 0;
+    let mut eof = false;
+
+    while !eof {
+        let mut batch: Vec<Vec<u8>> = Vec::with_capacity(batch_chunks);
+        while batch.len() < batch_chunks {
+            let mut buf = vec![0u8; chunk_size];
+            let n = read_full(reader, &mut buf)?;
+            if n == 0 {
+                eof = true;
+                break;
+            }
+            buf.truncate(n);
+            total = add_size(total, n as u64)?;
+            batch.push(buf);
+            if n < chunk_size {
+                eof = true;
+                break;
+            }
+        }
+
+        let base = chunks.len();
+        let metas = batch
+            .par_iter()
+            .enumerate()
+            .map(|(i, data)| write_chunk(out_dir, file_name, suffix, 1, base + i, data, key))
+            .collect::<Result<Vec<_>, _>>()?;
+        chunks.extend(metas);
+    }
+    Ok((chunks, total))
+}
+
+// Gear table for content-defined chunking: fixed pseudo-random values (splitmix64),
+// generated at compile time so boundaries are reproducible across builds.
+const GEAR: [u64; 256] = {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0x6476_656c_2d63_6463; // "dvel-cdc"
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+};
+
+/// FastCDC-style cut point: gear-hash from `min`, stricter mask before `avg`,
+/// looser mask after it, forced cut at `max` (or end of data).
+fn cdc_cut(data: &[u8], min: usize, avg: usize, max: usize) -> usize {
+    let n = data.len();
+    if n <= min {
+        return n;
+    }
+    let end = n.min(max);
+    let normal = avg.min(end);
+    // Mask the high bits: with a left-shifting gear hash they cover the widest byte window.
+    let bits = avg.ilog2();
+    let mask_s = !0u64 << (64 - (bits + 1).min(63));
+    let mask_l = !0u64 << (64 - bits.saturating_sub(1).max(1));
+
+    let mut h: u64 = 0;
+    let mut i = min;
+    while i < normal {
+        h = (h << 1).wrapping_add(GEAR[data[i] as usize]);
+        if h & mask_s == 0 {
+            return i + 1;
+        }
+        i += 1;
+    }
+    while i < end {
+        h = (h << 1).wrapping_add(GEAR[data[i] as usize]);
+        if h & mask_l == 0 {
+            return i + 1;
+        }
+        i += 1;
+    }
+    end
+}
+
+/// Content-defined chunking: boundaries follow the content (rolling gear hash), so an
+/// insertion only disturbs nearby chunks and unchanged regions keep their hashes.
+/// Produces a v2 manifest (per-chunk lengths); `chunk_size` records `max`, which may not
+/// exceed `MAX_CDC_CHUNK`.
+pub fn chunk_file_cdc(
+    input: &Path,
+    out_dir: &Path,
+    min: usize,
+    avg: usize,
+    max: usize,
+) -> Result<Manifest, StorageError> {
+    if min == 0 || min > avg || avg > max {
+        return Err(StorageError::InvalidManifest(
+            "cdc sizes must satisfy 0 < min <= avg <= max".into(),
+        ));
+    }
+    if max > MAX_CDC_CHUNK {
+        return Err(StorageError::InvalidManifest(
+            "cdc max exceeds MAX_CDC_CHUNK".into(),
+        ));
+    }
+    let capacity = max.checked_mul(2).ok_or(StorageError::SizeOverflow)?;
+    fs::create_dir_all(out_dir)?;
+    let file_name = input
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| StorageError::InvalidManifest("invalid file name".into()))?
+        .to_string();
+
+    let mut f = File::open(input)?;
+    let mut pending: Vec<u8> = Vec::with_capacity(capacity);
+    let mut read_buf = vec![0u8; max];
+    let mut eof = false;
+    let mut chunks = Vec::new();
+    let mut total: u64 = 0;
+
+    loop {
+        while !eof && pending.len() < max {
+            let n = f.read(&mut read_buf)?;
+            if n == 0 {
+                eof = true;
+            } else {
+                pending.extend_from_slice(&read_buf[..n]);
+            }
+        }
+        if pending.is_empty() {
+            break;
+        }
+        let cut = cdc_cut(&pending, min, avg, max);
+        total = add_size(total, cut as u64)?;
+        chunks.push(write_chunk(
+            out_dir,
+            &file_name,
+            None,
+            2,
+            chunks.len(),
+            &pending[..cut],
+            None,
+        )?);
+        pending.drain(..cut);
     }
 
     Ok(Manifest {
-        version: 1,
+        version: 2,
         file_name,
         total_size: total,
-        chunk_size: chunk_size as u64,
+        chunk_size: max as u64,
         chunks,
-        signer: None,
-        signature: None,
+        chunk_suffix: None,
+        encryption: None,
+        chunk_runs: false,
+        metadata: BTreeMap::new(),
+        parity: None,
+        signers: Vec::new(),
     })
 }
 
@@ -219,23 +1013,92 @@ pub fn write_manifest(manifest: &Manifest, path: &Path) -> Result<(), StorageErr
     Ok(())
 }
 
+/// `write_manifest` using `Manifest::to_reproducible_string`.
+pub fn write_manifest_reproducible(manifest: &Manifest, path: &Path) -> Result<(), StorageError> {
+    fs::write(path, manifest.to_reproducible_string())?;
+    Ok(())
+}
+
+/// Prefix reserved for experimental/optional manifest fields (`x-<name>:<value>`).
+/// Relaxed readers skip such lines; they are never part of the canonical bytes.
+pub const MANIFEST_EXPERIMENTAL_PREFIX: &str = "x-";
+
+/// How `parse_manifest` treats lines it does not recognise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestParseMode {
+    /// Every unknown line is an error.
+    Strict,
+    /// Well-formed `x-<name>:` lines are ignored; any other unknown line is still an error.
+    Relaxed,
+}
+
+fn is_experimental_line(line: &str) -> bool {
+    line.strip_prefix(MANIFEST_EXPERIMENTAL_PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+        .is_some_and(|(name, _)| {
+            !name.is_empty()
+                && name
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        })
+}
+
+/// Strict read: see `read_manifest_with_mode` to tolerate experimental fields.
 pub fn read_manifest(path: &Path) -> Result<Manifest, StorageError> {
-    let text = fs::read_to_string(path)?;
+    read_manifest_with_mode(path, ManifestParseMode::Strict)
+}
+
+pub fn read_manifest_with_mode(
+    path: &Path,
+    mode: ManifestParseMode,
+) -> Result<Manifest, StorageError> {
+    parse_manifest(&fs::read_to_string(path)?, mode)
+}
+
+/// v1: "<hex>", v2: "<hex>:<len>"; lengths are validated after the magic is known.
+fn parse_chunk_entry(entry: &str) -> Result<ChunkMeta, StorageError> {
+    let (hex_part, len) = match entry.split_once(':') {
+        Some((h, l)) => (
+            h,
+            l.parse::<u64>()
+                .map_err(|_| StorageError::InvalidManifest("bad chunk length".into()))?,
+        ),
+        None => (entry, 0),
+    };
+    let hash = hex_to_array::<32>(hex_part)?;
+    Ok(ChunkMeta { hash, len })
+}
+
+pub fn parse_manifest(text: &str, mode: ManifestParseMode) -> Result<Manifest, StorageError> {
     let mut file_name: Option<String> = None;
     let mut total_size: Option<u64> = None;
     let mut chunk_size: Option<u64> = None;
+    let mut chunk_suffix: Option<String> = None;
+    let mut encryption: Option<ChunkEncryption> = None;
+    let mut chunk_runs = false;
+    let mut metadata: BTreeMap<String, String> = BTreeMap::new();
     let mut chunks: Vec<ChunkMeta> = Vec::new();
-    let mut signer: Option<PublicKey> = None;
-    let mut signature: Option<Signature> = None;
+    let mut signers: Vec<(PublicKey, Signature)> = Vec::new();
+    let mut pending_signer: Option<PublicKey> = None;
     let mut declared_chunks: Option<usize> = None;
+    // Parsed parity so far and its declared shard count `m`.
+    let mut parity: Option<(ParityInfo, usize)> = None;
+    let mut version: u8 = 1;
 
     for line in text.lines() {
+        // Manifests written on Windows (CRLF) or by hand may carry a stray '\r'
+        // or trailing blanks; none are ever part of a field value.
+        let line = line.trim_end();
         if line.is_empty() {
             continue;
         }
         if line == MANIFEST_MAGIC {
             continue;
         }
+        if line == MANIFEST_MAGIC_V2 {
+            version = 2;
+            continue;
+        }
         if let Some(rest) = line.strip_prefix("file_name:") {
             file_name = Some(rest.to_string());
             continue;
@@ -248,21 +1111,112 @@ pub fn read_manifest(path: &Path) -> Result<Manifest, StorageError> {
             chunk_size = rest.parse::<u64>().ok();
             continue;
         }
+        if let Some(rest) = line.strip_prefix("chunk_suffix:") {
+            check_chunk_suffix(rest)?;
+            chunk_suffix = Some(rest.to_string());
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("encryption:") {
+            encryption = match rest {
+                "xchacha20poly1305" => Some(ChunkEncryption::XChaCha20Poly1305),
+                _ => return Err(StorageError::InvalidManifest("unknown encryption".into())),
+            };
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("meta:") {
+            let (key, value) = rest
+                .split_once('=')
+                .ok_or_else(|| StorageError::InvalidManifest("bad metadata line".into()))?;
+            check_metadata_entry(key, value)?;
+            if metadata
+                .insert(key.to_string(), value.to_string())
+                .is_some()
+            {
+                return Err(StorageError::InvalidManifest(format!(
+                    "duplicate metadata key {}",
+                    key
+                )));
+            }
+            continue;
+        }
         if let Some(rest) = line.strip_prefix("chunks:") {
             declared_chunks = rest.parse::<usize>().ok();
             continue;
         }
+        if let Some(rest) = line.strip_prefix("r:") {
+            let (count, entry) = rest
+                .split_once(':')
+                .ok_or_else(|| StorageError::InvalidManifest("bad chunk run".into()))?;
+            let count = count
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n >= 2)
+                .ok_or_else(|| StorageError::InvalidManifest("bad chunk run count".into()))?;
+            let meta = parse_chunk_entry(entry)?;
+            // Bound the expansion by the declared count (which precedes chunk lines).
+            if declared_chunks.is_none_or(|n| chunks.len().saturating_add(count) > n) {
+                return Err(StorageError::InvalidManifest("chunk count mismatch".into()));
+            }
+            chunks.extend(std::iter::repeat_n(meta, count));
+            chunk_runs = true;
+            continue;
+        }
         if let Some(rest) = line.strip_prefix("h:") {
-            let hash = hex_to_array::<32>(rest)?;
-            chunks.push(ChunkMeta { hash });
+            chunks.push(parse_chunk_entry(rest)?);
             continue;
         }
-        if let Some(rest) = line.strip_prefix("signer:") {
-            signer = Some(hex_to_array::<32>(rest)?);
+        if let Some(rest) = line.strip_prefix("parity:") {
+            let counts = rest
+                .split_once(':')
+                .and_then(|(k, m)| Some((k.parse::<usize>().ok()?, m.parse::<usize>().ok()?)));
+            let (k, m) = match counts {
+                Some((k, m)) if parity.is_none() && k >= 1 && m >= 1 => (k, m),
+                _ => return Err(StorageError::InvalidManifest("bad parity line".into())),
+            };
+            if k.saturating_add(m) > MAX_PARITY_SHARDS {
+                return Err(StorageError::InvalidManifest(
+                    "too many parity shards".into(),
+                ));
+            }
+            let info = ParityInfo {
+                data_shards: k,
+                hashes: Vec::with_capacity(m),
+            };
+            parity = Some((info, m));
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("p:") {
+            match &mut parity {
+                Some((info, m)) if info.hashes.len() < *m => {
+                    info.hashes.push(hex_to_array::<32>(rest)?);
+                }
+                _ => {
+                    return Err(StorageError::InvalidManifest(
+                        "unexpected parity hash".into(),
+                    ));
+                }
+            }
+            continue;
+        }
+        // Signatures come as signer:/signature: line pairs (a legacy single-signer
+        // manifest is simply one pair).
+        if let Some(rest) = line.strip_prefix("signer:") {
+            if pending_signer.is_some() {
+                return Err(StorageError::InvalidManifest(
+                    "signer without signature".into(),
+                ));
+            }
+            pending_signer = Some(hex_to_array::<32>(rest)?);
             continue;
         }
         if let Some(rest) = line.strip_prefix("signature:") {
-            signature = Some(hex_to_array::<64>(rest)?);
+            let signer = pending_signer
+                .take()
+                .ok_or_else(|| StorageError::InvalidManifest("signature without signer".into()))?;
+            signers.push((signer, hex_to_array::<64>(rest)?));
+            continue;
+        }
+        if mode == ManifestParseMode::Relaxed && is_experimental_line(line) {
             continue;
         }
         return Err(StorageError::InvalidManifest("unknown line".into()));
@@ -275,82 +1229,552 @@ pub fn read_manifest(path: &Path) -> Result<Manifest, StorageError> {
     let cs =
         chunk_size.ok_or_else(|| StorageError::InvalidManifest("missing chunk_size".into()))?;
 
+    if pending_signer.is_some() {
+        return Err(StorageError::InvalidManifest(
+            "signer without signature".into(),
+        ));
+    }
     if let Some(n) = declared_chunks && n != chunks.len() {
         return Err(StorageError::InvalidManifest("chunk count mismatch".into()));
     }
+    let parity = match parity {
+        None => None,
+        Some((info, m)) if info.hashes.len() == m && info.data_shards == chunks.len() => Some(info),
+        Some(_) => {
+            return Err(StorageError::InvalidManifest(
+                "parity does not match chunks".into(),
+            ));
+        }
+    };
+
+    if version >= 2 {
+        if declared_size(&chunks)? != ts {
+            return Err(StorageError::InvalidManifest(
+                "chunk lengths do not sum to total_size".into(),
+            ));
+        }
+    } else {
+        // v1 lengths are implicit: every chunk is chunk_size except the (possibly short) last one.
+        let n = chunks.len() as u64;
+        for (i, c) in chunks.iter_mut().enumerate() {
+            c.len = if (i as u64) + 1 < n {
+                cs
+            } else {
+                ts.saturating_sub(cs.saturating_mul(n.saturating_sub(1)))
+            };
+        }
+    }
 
     Ok(Manifest {
-        version: 1,
+        version,
         file_name: fname,
         total_size: ts,
         chunk_size: cs,
         chunks,
-        signer,
-        signature,
+        chunk_suffix,
+        encryption,
+        chunk_runs,
+        metadata,
+        parity,
+        signers,
     })
 }
 
-pub fn sign_manifest_inplace(
-    manifest: &mut Manifest,
+fn sign_canonical(
+    manifest: &Manifest,
     secret_key: &[u8; 32],
-) -> Result<(), StorageError> {
+) -> Result<(PublicKey, Signature), StorageError> {
+    // Entries inserted directly into the map bypass set_metadata; never sign what
+    // parse_manifest would reject.
+    for (k, v) in &manifest.metadata {
+        check_metadata_entry(k, v)?;
+    }
     let sk = SecretKey::from_bytes(secret_key).map_err(|_| StorageError::SignatureInvalid)?;
     let pk: DalekPublicKey = (&sk).into();
     let esk = ExpandedSecretKey::from(&sk);
     let sig = esk.sign(&manifest.canonical_bytes(), &pk);
+    Ok((pk.to_bytes(), sig.to_bytes()))
+}
 
-    manifest.signer = Some(pk.to_bytes());
-    manifest.signature = Some(sig.to_bytes());
+/// Sign as the sole signer (replaces any existing signatures).
+pub fn sign_manifest_inplace(
+    manifest: &mut Manifest,
+    secret_key: &[u8; 32],
+) -> Result<(), StorageError> {
+    let pair = sign_canonical(manifest, secret_key)?;
+    manifest.signers = vec![pair];
     Ok(())
 }
 
-pub fn verify_manifest_signature(manifest: &Manifest) -> Result<(), StorageError> {
-    let signer = manifest.signer.ok_or(StorageError::SignatureMissing)?;
-    let sig_bytes = manifest.signature.ok_or(StorageError::SignatureMissing)?;
+/// Add a co-signature. The signing input is the unsigned canonical bytes, so signatures are
+/// independent of each other and of their order. Re-signing with the same key replaces it.
+pub fn sign_manifest_add(
+    manifest: &mut Manifest,
+    secret_key: &[u8; 32],
+) -> Result<(), StorageError> {
+    let (pk, sig) = sign_canonical(manifest, secret_key)?;
+    match manifest.signers.iter_mut().find(|(s, _)| *s == pk) {
+        Some(entry) => entry.1 = sig,
+        None => manifest.signers.push((pk, sig)),
+    }
+    Ok(())
+}
 
-    let pk = DalekPublicKey::from_bytes(&signer).map_err(|_| StorageError::SignatureInvalid)?;
-    let sig = DalekSignature::from_bytes(&sig_bytes).map_err(|_| StorageError::SignatureInvalid)?;
-    pk.verify_strict(&manifest.canonical_bytes(), &sig)
+/// Sign with an external signer (e.g. an HSM): `sign_fn` receives the canonical
+/// manifest bytes and returns the ed25519 signature over them.
+pub fn sign_manifest_with(
+    manifest: &mut Manifest,
+    signer_pubkey: PublicKey,
+    sign_fn: impl FnOnce(&[u8]) -> Signature,
+) {
+    let sig = sign_fn(&manifest.canonical_bytes());
+    manifest.signers = vec![(signer_pubkey, sig)];
+}
+
+fn verify_one_signature(
+    canonical: &[u8],
+    signer: &PublicKey,
+    sig_bytes: &Signature,
+) -> Result<(), StorageError> {
+    let pk = DalekPublicKey::from_bytes(signer).map_err(|_| StorageError::SignatureInvalid)?;
+    let sig = DalekSignature::from_bytes(sig_bytes).map_err(|_| StorageError::SignatureInvalid)?;
+    pk.verify_strict(canonical, &sig)
         .map_err(|_| StorageError::SignatureInvalid)
 }
 
+/// Requires at least one signature; every present signature must be valid.
+pub fn verify_manifest_signature(manifest: &Manifest) -> Result<(), StorageError> {
+    if manifest.signers.is_empty() {
+        return Err(StorageError::SignatureMissing);
+    }
+    let canonical = manifest.canonical_bytes();
+    for (signer, sig) in &manifest.signers {
+        verify_one_signature(&canonical, signer, sig)?;
+    }
+    Ok(())
+}
+
+/// Threshold check: Ok iff at least `threshold` distinct keys from `allowed` produced valid
+/// signatures. Signatures by keys outside `allowed` (or invalid ones) are not counted.
+pub fn verify_manifest_signatures(
+    manifest: &Manifest,
+    threshold: usize,
+    allowed: &[PublicKey],
+) -> Result<(), StorageError> {
+    let canonical = manifest.canonical_bytes();
+    let mut counted: Vec<PublicKey> = Vec::new();
+    for (signer, sig) in &manifest.signers {
+        if !allowed.contains(signer) || counted.contains(signer) {
+            continue;
+        }
+        if verify_one_signature(&canonical, signer, sig).is_ok() {
+            counted.push(*signer);
+        }
+    }
+    if counted.len() < threshold {
+        return Err(StorageError::ThresholdNotMet {
+            valid: counted.len(),
+            required: threshold,
+        });
+    }
+    Ok(())
+}
+
+/// Verify every chunk against the manifest. With the `parallel` feature chunks are read and
+/// hashed concurrently; on corruption the lowest failing index is reported either way.
 pub fn verify_chunks(manifest: &Manifest, chunk_dir: &Path) -> Result<(), StorageError> {
-    let mut total: u64 = 0;
-    for (idx, meta) in manifest.chunks.iter().enumerate() {
-        let p = chunk_path(chunk_dir, &manifest.file_name, idx);
-        let data = fs::read(&p)?;
-        let hash = sha256_bytes(&data);
-        total = total.saturating_add(data.len() as u64);
-        if hash != meta.hash {
-            return Err(StorageError::HashMismatch { index: idx });
+    verify_chunks_named(manifest, chunk_dir, None)
+}
+
+/// `verify_chunks`, then a scan of `chunk_dir` for leftover chunk files of the same name
+/// (e.g. from an earlier, larger upload). The lowest unlisted index is reported as
+/// `UnexpectedChunk`. Files with other names are ignored; `verify_chunks` stays lenient.
+pub fn verify_chunks_strict(manifest: &Manifest, chunk_dir: &Path) -> Result<(), StorageError> {
+    verify_chunks(manifest, chunk_dir)?;
+    let mut unexpected: Option<usize> = None;
+    for entry in fs::read_dir(chunk_dir)? {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if let Some(index) =
+            chunk_index_from_filename(&name, &manifest.file_name, manifest.chunk_suffix.as_deref())
+            && index >= manifest.chunks.len()
+        {
+            unexpected = Some(unexpected.map_or(index, |u| u.min(index)));
         }
     }
+    match unexpected {
+        Some(index) => Err(StorageError::UnexpectedChunk { index }),
+        None => Ok(()),
+    }
+}
+
+/// Strictly single-threaded verification (one chunk at a time, in order).
+pub fn verify_chunks_sequential(manifest: &Manifest, chunk_dir: &Path) -> Result<(), StorageError> {
+    verify_chunks_sequential_named(manifest, chunk_dir, &manifest.file_name, None)
+}
+
+/// Like `verify_chunks`, but chunk files may be stored under a different base name than
+/// `manifest.file_name` (e.g. a generic cache name). Hashes still come from the manifest.
+pub fn verify_chunks_named(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    chunk_name_override: Option<&str>,
+) -> Result<(), StorageError> {
+    let base = chunk_name_override.unwrap_or(&manifest.file_name);
+    verify_chunks_keyed(manifest, chunk_dir, base, None)
+}
+
+/// `verify_chunks` with `opts.jobs` worker threads (`parallel` feature; sequential otherwise).
+pub fn verify_chunks_with_options(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    opts: &IoOptions,
+) -> Result<(), StorageError> {
+    opts.check()?;
+    #[cfg(feature = "parallel")]
+    {
+        with_jobs(opts.jobs, || {
+            verify_chunks_keyed(manifest, chunk_dir, &manifest.file_name, None)
+        })
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        verify_chunks_keyed(manifest, chunk_dir, &manifest.file_name, None)
+    }
+}
+
+/// `verify_chunks` for a manifest written by `chunk_file_to_dir_encrypted`: each chunk is
+/// decrypted under `key`, then hashed. A wrong key or tampered file is `DecryptFailed`.
+#[cfg(feature = "encryption")]
+pub fn verify_chunks_with_key(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    key: &[u8; 32],
+) -> Result<(), StorageError> {
+    verify_chunks_keyed(manifest, chunk_dir, &manifest.file_name, Some(key))
+}
+
+fn verify_chunks_keyed(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    base: &str,
+    key: Option<&[u8; 32]>,
+) -> Result<(), StorageError> {
+    // Reject wrapping declared lengths before reading any chunk file.
+    declared_size(&manifest.chunks)?;
+    #[cfg(feature = "parallel")]
+    {
+        verify_chunks_parallel_named(manifest, chunk_dir, base, key)
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        verify_chunks_sequential_named(manifest, chunk_dir, base, key)
+    }
+}
+
+/// Read one chunk file (decrypting it if the manifest says so) and check it against its
+/// manifest hash.
+fn read_verified_chunk(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    base: &str,
+    idx: usize,
+    key: Option<&[u8; 32]>,
+) -> Result<Vec<u8>, StorageError> {
+    let raw = fs::read(chunk_path_with_suffix(
+        chunk_dir,
+        base,
+        manifest.chunk_suffix.as_deref(),
+        idx,
+    ))?;
+    let data = open_chunk(manifest, idx, key, raw)?;
+    if chunk_hash(manifest.version, &data) != manifest.chunks[idx].hash {
+        return Err(StorageError::HashMismatch { index: idx });
+    }
+    Ok(data)
+}
+
+/// Read and hash one chunk; returns its length on success.
+fn verify_one_chunk(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    base: &str,
+    idx: usize,
+    key: Option<&[u8; 32]>,
+) -> Result<u64, StorageError> {
+    Ok(read_verified_chunk(manifest, chunk_dir, base, idx, key)?.len() as u64)
+}
+
+fn verify_chunks_sequential_named(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    base: &str,
+    key: Option<&[u8; 32]>,
+) -> Result<(), StorageError> {
+    let mut total: u64 = 0;
+    for idx in 0..manifest.chunks.len() {
+        let len = verify_one_chunk(manifest, chunk_dir, base, idx, key)?;
+        total = add_size(total, len)?;
+    }
     if total != manifest.total_size {
         return Err(StorageError::InvalidManifest("total_size mismatch".into()));
     }
     Ok(())
 }
 
+/// Parallel sweep: every chunk is checked, then results are scanned in index order so the
+/// reported error (and the size sum) do not depend on thread scheduling.
+#[cfg(feature = "parallel")]
+fn verify_chunks_parallel_named(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    base: &str,
+    key: Option<&[u8; 32]>,
+) -> Result<(), StorageError> {
+    use rayon::prelude::*;
+
+    let results: Vec<Result<u64, StorageError>> = (0..manifest.chunks.len())
+        .into_par_iter()
+        .map(|idx| verify_one_chunk(manifest, chunk_dir, base, idx, key))
+        .collect();
+
+    let mut total: u64 = 0;
+    for r in results {
+        total = add_size(total, r?)?;
+    }
+    if total != manifest.total_size {
+        return Err(StorageError::InvalidManifest("total_size mismatch".into()));
+    }
+    Ok(())
+}
+
+/// Indices of the chunks covering bytes `[start, end)` of the file, in order; the planning
+/// step for serving a byte range. Offsets come from the per-chunk lengths, so fixed and
+/// content-defined manifests both work. `end` is clamped to `total_size`; an empty or
+/// out-of-file range yields no chunks.
+pub fn chunks_for_range(manifest: &Manifest, start: u64, end: u64) -> Vec<usize> {
+    let end = end.min(manifest.total_size);
+    let mut out = Vec::new();
+    if start >= end {
+        return out;
+    }
+    let mut offset: u64 = 0;
+    for (idx, c) in manifest.chunks.iter().enumerate() {
+        if offset >= end {
+            break;
+        }
+        let chunk_end = offset.saturating_add(c.len);
+        if chunk_end > start {
+            out.push(idx);
+        }
+        offset = chunk_end;
+    }
+    out
+}
+
+/// Verify the files in `chunk_dir` against a `SHA256SUMS`-style file (as written by
+/// `sha256sum`): one `<hex>  <filename>` line per file, `<hex> *<filename>` also accepted.
+/// Digests are plain SHA-256 of the file contents, independent of any dvel manifest.
+/// Listed names must be plain file names inside `chunk_dir`. Returns the number of files
+/// checked; the first mismatch or missing file is an error.
+pub fn verify_chunks_against_sums(
+    chunk_dir: &Path,
+    sums_file: &Path,
+) -> Result<usize, StorageError> {
+    let text = fs::read_to_string(sums_file)?;
+    let mut checked = 0;
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        let invalid = |reason: &str| StorageError::InvalidSums {
+            line: idx + 1,
+            reason: reason.into(),
+        };
+        let (hex_part, rest) = line
+            .split_once(' ')
+            .ok_or_else(|| invalid("expected '<hex>  <filename>'"))?;
+        let expected =
+            hex_to_array::<32>(hex_part).map_err(|_| invalid("digest is not 64 hex chars"))?;
+        let name = rest
+            .strip_prefix(' ')
+            .or_else(|| rest.strip_prefix('*'))
+            .ok_or_else(|| invalid("expected two spaces or ' *' before the filename"))?;
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(invalid(
+                "filename must name a file inside the chunk directory",
+            ));
+        }
+
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut File::open(chunk_dir.join(name))?, &mut hasher)?;
+        let actual: Hash = hasher.finalize().into();
+        if actual != expected {
+            return Err(StorageError::ChecksumMismatch { file: name.into() });
+        }
+        checked += 1;
+    }
+    Ok(checked)
+}
+
+/// Ingest-side gate: read `reader` chunk by chunk (the manifest's per-chunk lengths, i.e.
+/// `chunk_size` for fixed-size manifests) and compare each hash as it arrives. Chunks are
+/// hashed through a fixed 64 KiB buffer, so memory does not depend on the (untrusted)
+/// declared lengths. Fails at the first differing chunk, or if the stream ends early or
+/// carries extra bytes, with `StreamMismatch`.
+pub fn verify_stream_against_manifest<R: Read>(
+    manifest: &Manifest,
+    mut reader: R,
+) -> Result<(), StorageError> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut consumed: u64 = 0;
+    for (index, meta) in manifest.chunks.iter().enumerate() {
+        // Same digest as `chunk_hash`, fed incrementally.
+        let mut hasher = Sha256::new();
+        if manifest.version >= 2 {
+            hasher.update(meta.len.to_le_bytes());
+        }
+        let mut remaining = meta.len;
+        while remaining > 0 {
+            let want = &mut buf[..remaining.min(64 * 1024) as usize];
+            let n = read_full(&mut reader, want)?;
+            consumed = add_size(consumed, n as u64)?;
+            if n != want.len() {
+                return Err(StorageError::StreamMismatch { index, consumed });
+            }
+            hasher.update(&want[..n]);
+            remaining -= n as u64;
+        }
+        let actual: Hash = hasher.finalize().into();
+        if actual != meta.hash {
+            return Err(StorageError::StreamMismatch { index, consumed });
+        }
+    }
+    let mut probe = [0u8; 1];
+    if read_full(&mut reader, &mut probe)? != 0 {
+        return Err(StorageError::StreamMismatch {
+            index: manifest.chunks.len(),
+            consumed: add_size(consumed, 1)?,
+        });
+    }
+    Ok(())
+}
+
 pub fn reassemble(
     manifest: &Manifest,
     chunk_dir: &Path,
     output: &Path,
 ) -> Result<(), StorageError> {
+    reassemble_named(manifest, chunk_dir, output, None)
+}
+
+/// Like `reassemble`, reading chunk files named under `chunk_name_override` when given.
+pub fn reassemble_named(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    output: &Path,
+    chunk_name_override: Option<&str>,
+) -> Result<(), StorageError> {
+    let base = chunk_name_override.unwrap_or(&manifest.file_name);
     let mut out = File::create(output)?;
+    reassemble_into(manifest, chunk_dir, base, &mut out, None)
+}
+
+/// `reassemble` for encrypted chunks: decrypt under `key`, hash-check, then write.
+#[cfg(feature = "encryption")]
+pub fn reassemble_with_key(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    output: &Path,
+    key: &[u8; 32],
+) -> Result<(), StorageError> {
+    let mut out = File::create(output)?;
+    reassemble_into(
+        manifest,
+        chunk_dir,
+        &manifest.file_name,
+        &mut out,
+        Some(key),
+    )
+}
+
+/// Stream the verified, reassembled bytes into any writer.
+pub fn reassemble_to_writer<W: Write>(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    writer: &mut W,
+) -> Result<(), StorageError> {
+    reassemble_into(manifest, chunk_dir, &manifest.file_name, writer, None)
+}
+
+fn reassemble_into<W: Write>(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    base: &str,
+    out: &mut W,
+    key: Option<&[u8; 32]>,
+) -> Result<(), StorageError> {
     for idx in 0..manifest.chunks.len() {
-        let p = chunk_path(chunk_dir, &manifest.file_name, idx);
-        let mut f = File::open(&p)?;
-        let mut buf = Vec::new();
-        f.read_to_end(&mut buf)?;
-        let hash = sha256_bytes(&buf);
-        if hash != manifest.chunks[idx].hash {
-            return Err(StorageError::HashMismatch { index: idx });
-        }
+        let buf = read_verified_chunk(manifest, chunk_dir, base, idx, key)?;
         out.write_all(&buf)?;
     }
     Ok(())
 }
 
+/// `Read` over the reassembled file that verifies each chunk as it is loaded. A corrupt
+/// chunk surfaces as an `InvalidData` IO error wrapping `StorageError::HashMismatch`, after
+/// all bytes of the preceding (good) chunks have been yielded.
+pub struct VerifyingChunkReader<'a> {
+    manifest: &'a Manifest,
+    chunk_dir: PathBuf,
+    next_index: usize,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<'a> VerifyingChunkReader<'a> {
+    pub fn new(manifest: &'a Manifest, chunk_dir: &Path) -> Self {
+        VerifyingChunkReader {
+            manifest,
+            chunk_dir: chunk_dir.to_path_buf(),
+            next_index: 0,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl Read for VerifyingChunkReader<'_> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos == self.buf.len() {
+            let idx = self.next_index;
+            if idx >= self.manifest.chunks.len() {
+                return Ok(0);
+            }
+            self.buf = read_verified_chunk(
+                self.manifest,
+                &self.chunk_dir,
+                &self.manifest.file_name,
+                idx,
+                None,
+            )
+            .map_err(|e| match e {
+                StorageError::Io(io) => io,
+                other => std::io::Error::new(std::io::ErrorKind::InvalidData, other),
+            })?;
+            self.pos = 0;
+            self.next_index += 1;
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
 pub fn manifest_hash_from_file(manifest_path: &Path) -> Result<Hash, StorageError> {
     let m = read_manifest(manifest_path)?;
     Ok(m.hash())
@@ -361,6 +1785,104 @@ pub fn chunk_merkle_root_from_file(manifest_path: &Path) -> Result<Option<Hash>,
     Ok(m.chunk_merkle_root())
 }
 
+/// Exercise chunk -> sign -> write -> read -> verify -> reassemble on synthetic data in a
+/// scratch directory under the system temp dir. Returns the first failing invariant.
+pub fn run_selfcheck() -> Result<(), String> {
+    static RUN: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+    let run = RUN.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!("dvel-selfcheck-{}-{}", std::process::id(), run));
+    fs::create_dir_all(&dir).map_err(|e| format!("create scratch dir: {}", e))?;
+    let result = selfcheck_in(&dir);
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+fn selfcheck_in(dir: &Path) -> Result<(), String> {
+    fn check(ok: bool, invariant: &str) -> Result<(), String> {
+        if ok {
+            Ok(())
+        } else {
+            Err(invariant.to_string())
+        }
+    }
+    let step = |what: &'static str| move |e: StorageError| format!("{}: {}", what, e);
+
+    // Deterministic, non-repeating input; not a multiple of any chunk size below.
+    let mut x = 0x9e37_79b9_7f4a_7c15u64;
+    let data: Vec<u8> = (0..50_001)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            (x >> 24) as u8
+        })
+        .collect();
+    let input = dir.join("selfcheck.bin");
+    fs::write(&input, &data).map_err(|e| format!("write input: {}", e))?;
+
+    let fixed = chunk_file_to_dir(&input, dir, 4096).map_err(step("chunk"))?;
+    let cdc_dir = dir.join("cdc");
+    fs::create_dir_all(&cdc_dir).map_err(|e| format!("create cdc dir: {}", e))?;
+    let cdc = chunk_file_cdc(&input, &cdc_dir, 1024, 4096, 16384).map_err(step("cdc chunk"))?;
+
+    for (label, mut manifest, chunk_dir) in [("fixed", fixed, dir), ("cdc", cdc, cdc_dir.as_path())]
+    {
+        let sum: u64 = manifest.chunks.iter().map(|c| c.len).sum();
+        check(
+            sum == data.len() as u64,
+            &format!("{}: chunk lengths sum to file size", label),
+        )?;
+
+        sign_manifest_inplace(&mut manifest, &[0x5a; 32]).map_err(step("sign"))?;
+        let mpath = manifest_path(chunk_dir, &manifest.file_name);
+        write_manifest(&manifest, &mpath).map_err(step("write manifest"))?;
+        let read = read_manifest(&mpath).map_err(step("read manifest"))?;
+        check(
+            read.to_string_with_signature() == manifest.to_string_with_signature(),
+            &format!("{}: manifest text round-trips", label),
+        )?;
+
+        verify_manifest_signature(&read).map_err(step("verify signature"))?;
+        verify_chunks(&read, chunk_dir).map_err(step("verify chunks"))?;
+
+        let out = chunk_dir.join("selfcheck.out");
+        reassemble(&read, chunk_dir, &out).map_err(step("reassemble"))?;
+        let back = fs::read(&out).map_err(|e| format!("read output: {}", e))?;
+        check(
+            back == data,
+            &format!("{}: reassembled bytes equal input", label),
+        )?;
+
+        let file_hash = manifest_hash_from_file(&mpath).map_err(step("manifest hash"))?;
+        check(
+            file_hash == manifest.hash(),
+            &format!("{}: manifest.hash() matches manifest_hash_from_file", label),
+        )?;
+        let file_root = chunk_merkle_root_from_file(&mpath).map_err(step("merkle root"))?;
+        check(
+            file_root.is_some() && file_root == manifest.chunk_merkle_root(),
+            &format!(
+                "{}: chunk_merkle_root matches chunk_merkle_root_from_file",
+                label
+            ),
+        )?;
+
+        // Corruption must be caught.
+        let p = chunk_path(chunk_dir, &read.file_name, 1);
+        let mut chunk = fs::read(&p).map_err(|e| format!("read chunk: {}", e))?;
+        chunk[0] ^= 0x80;
+        fs::write(&p, &chunk).map_err(|e| format!("write chunk: {}", e))?;
+        check(
+            matches!(
+                verify_chunks(&read, chunk_dir),
+                Err(StorageError::HashMismatch { index: 1 })
+            ),
+            &format!("{}: corrupt chunk 1 is detected", label),
+        )?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -424,6 +1946,873 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn sign_with_external_signer_verifies() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        fs::write(&input_path, sample_bytes(5000))?;
+
+        let mut manifest = chunk_file_to_dir(&input_path, dir.path(), 1024)?;
+        let sk = SecretKey::from_bytes(&[9u8; 32]).unwrap();
+        let pk: DalekPublicKey = (&sk).into();
+        let esk = ExpandedSecretKey::from(&sk);
+        sign_manifest_with(&mut manifest, pk.to_bytes(), |bytes| {
+            esk.sign(bytes, &pk).to_bytes()
+        });
+
+        assert_eq!(manifest.signers.len(), 1);
+        assert_eq!(manifest.signers[0].0, pk.to_bytes());
+        verify_manifest_signature(&manifest)?;
+
+        manifest.total_size += 1;
+        assert!(matches!(
+            verify_manifest_signature(&manifest),
+            Err(StorageError::SignatureInvalid)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn chunking_order_matches_sequential() -> Result<(), StorageError> {
+        // With `parallel` on, chunk_file_to_dir hashes across threads; the manifest must
+        // still list chunks in file order, identical to the sequential loop.
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        let data = sample_bytes(300 * 1024 + 17);
+        fs::write(&input_path, &data)?;
+
+        let manifest = chunk_file_to_dir(&input_path, &dir.path().join("a"), 1000)?;
+        let seq_dir = dir.path().join("b");
+        fs::create_dir_all(&seq_dir)?;
+        let (seq_chunks, seq_total) = chunk_stream_sequential(
+            &mut File::open(&input_path)?,
+            &seq_dir,
+            "sample.bin",
+            None,
+            1000,
+            None,
+        )?;
+
+        let hashes: Vec<_> = manifest.chunks.iter().map(|c| c.hash).collect();
+        let seq_hashes: Vec<_> = seq_chunks.iter().map(|c| c.hash).collect();
+        assert_eq!(hashes, seq_hashes);
+        assert_eq!(manifest.total_size, seq_total);
+        Ok(())
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn encrypted_chunks_round_trip_and_reject_wrong_key() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        let data = sample_bytes(5000);
+        fs::write(&input_path, &data)?;
+        let key = [9u8; 32];
+
+        let mut manifest = chunk_file_to_dir_encrypted(&input_path, dir.path(), 1024, &key)?;
+        let plain = chunk_file_to_dir(&input_path, &dir.path().join("plain"), 1024)?;
+        assert_eq!(
+            manifest.chunks.iter().map(|c| c.hash).collect::<Vec<_>>(),
+            plain.chunks.iter().map(|c| c.hash).collect::<Vec<_>>(),
+            "manifest keeps plaintext hashes"
+        );
+        assert_ne!(
+            fs::read(chunk_path(dir.path(), "sample.bin", 0))?,
+            data[..1024]
+        );
+
+        sign_manifest_inplace(&mut manifest, &[7u8; 32])?;
+        let mpath = manifest_path(dir.path(), &manifest.file_name);
+        write_manifest(&manifest, &mpath)?;
+        let loaded = read_manifest(&mpath)?;
+        assert_eq!(loaded.encryption, Some(ChunkEncryption::XChaCha20Poly1305));
+        verify_manifest_signature(&loaded)?;
+
+        verify_chunks_with_key(&loaded, dir.path(), &key)?;
+        let out_path = dir.path().join("rebuilt.bin");
+        reassemble_with_key(&loaded, dir.path(), &out_path, &key)?;
+        assert_eq!(fs::read(&out_path)?, data);
+
+        assert!(matches!(
+            reassemble_with_key(&loaded, dir.path(), &out_path, &[8u8; 32]),
+            Err(StorageError::DecryptFailed { index: 0 })
+        ));
+        assert!(matches!(
+            verify_chunks(&loaded, dir.path()),
+            Err(StorageError::KeyRequired)
+        ));
+
+        // The encryption line is signed: dropping it invalidates the signature.
+        let mut stripped = loaded.clone();
+        stripped.encryption = None;
+        assert!(verify_manifest_signature(&stripped).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn chunk_runs_shrink_sparse_manifest() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("disk.img");
+        let mut data = sample_bytes(3 * 1024);
+        data.extend(std::iter::repeat_n(0u8, 200 * 1024));
+        data.extend(sample_bytes(1500));
+        fs::write(&input_path, &data)?;
+
+        let plain = chunk_file_to_dir(&input_path, dir.path(), 1024)?;
+        let mut manifest = plain.clone().with_chunk_runs();
+        assert!(manifest.canonical_bytes().len() * 10 < plain.canonical_bytes().len());
+        assert_ne!(manifest.hash(), plain.hash());
+
+        sign_manifest_inplace(&mut manifest, &[7u8; 32])?;
+        let mpath = manifest_path(dir.path(), &manifest.file_name);
+        write_manifest(&manifest, &mpath)?;
+        let loaded = read_manifest(&mpath)?;
+        assert!(loaded.chunk_runs);
+        assert_eq!(loaded.chunks.len(), plain.chunks.len());
+        assert_eq!(
+            loaded.to_string_with_signature(),
+            manifest.to_string_with_signature()
+        );
+        verify_manifest_signature(&loaded)?;
+        verify_chunks(&loaded, dir.path())?;
+
+        let out_path = dir.path().join("rebuilt.img");
+        reassemble(&loaded, dir.path(), &out_path)?;
+        assert_eq!(fs::read(&out_path)?, data);
+
+        let text = String::from_utf8(plain.canonical_bytes()).unwrap();
+        let zero = hex::encode(chunk_hash(1, &[0u8; 1024]));
+        for bad in [format!("r:1:{}", zero), format!("r:999:{}", zero)] {
+            let forged = text.replacen(&format!("h:{}", zero), &bad, 1);
+            assert!(parse_manifest(&forged, ManifestParseMode::Strict).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn io_options_do_not_change_output() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        fs::write(&input_path, sample_bytes(20_000))?;
+
+        let base = chunk_file_to_dir(&input_path, &dir.path().join("a"), 1000)?;
+        let opts = IoOptions {
+            jobs: 2,
+            readahead: 3,
+            io_buffer: 100,
+        };
+        let tuned =
+            chunk_file_to_dir_with_options(&input_path, &dir.path().join("b"), 1000, None, &opts)?;
+        assert_eq!(tuned.canonical_bytes(), base.canonical_bytes());
+        verify_chunks_with_options(&tuned, &dir.path().join("b"), &opts)?;
+
+        let zero = IoOptions { jobs: 0, ..opts };
+        assert!(
+            chunk_file_to_dir_with_options(&input_path, &dir.path().join("c"), 1000, None, &zero)
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn metadata_is_signed_sorted_and_validated() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        fs::write(&input_path, sample_bytes(3000))?;
+        let mut manifest = chunk_file_to_dir(&input_path, dir.path(), 1024)?;
+        manifest.set_metadata("mime", "application/octet-stream")?;
+        manifest.set_metadata("created", "2024-05-01T12:00:00Z")?;
+        manifest.set_metadata("tag.team", "storage")?;
+        assert!(manifest.set_metadata("a=b", "x").is_err());
+        assert!(manifest.set_metadata("k", "line\nbreak").is_err());
+        assert!(manifest.set_metadata("", "x").is_err());
+        sign_manifest_inplace(&mut manifest, &[7u8; 32])?;
+
+        let mpath = manifest_path(dir.path(), &manifest.file_name);
+        write_manifest(&manifest, &mpath)?;
+        let text = fs::read_to_string(&mpath)?;
+        let created = text.find("meta:created=").unwrap();
+        assert!(created < text.find("meta:mime=").unwrap());
+        let loaded = read_manifest(&mpath)?;
+        assert_eq!(loaded.metadata, manifest.metadata);
+        verify_manifest_signature(&loaded)?;
+
+        let tampered = text.replace("meta:tag.team=storage", "meta:tag.team=other");
+        let forged = parse_manifest(&tampered, ManifestParseMode::Strict)?;
+        assert!(matches!(
+            verify_manifest_signature(&forged),
+            Err(StorageError::SignatureInvalid)
+        ));
+
+        let dup = text.replace("meta:mime=", "meta:created=");
+        assert!(parse_manifest(&dup, ManifestParseMode::Strict).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn tee_copies_input_while_chunking() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("ingest.bin");
+        let data = sample_bytes(70_000);
+        fs::write(&input_path, &data)?;
+
+        let copy_path = dir.path().join("working.bin");
+        let store = dir.path().join("store");
+        let manifest = chunk_file_tee(&input_path, &store, &copy_path, 4096)?;
+        assert_eq!(fs::read(&copy_path)?, data);
+
+        let plain = chunk_file_to_dir(&input_path, &dir.path().join("plain"), 4096)?;
+        assert_eq!(manifest.canonical_bytes(), plain.canonical_bytes());
+        verify_chunks(&manifest, &store)?;
+
+        assert!(chunk_file_tee(&input_path, &store, &input_path, 4096).is_err());
+        assert_eq!(fs::read(&input_path)?, data);
+        Ok(())
+    }
+
+    #[test]
+    fn stream_verification_stops_at_bad_chunk() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("upload.bin");
+        let data = sample_bytes(10_000);
+        fs::write(&input_path, &data)?;
+        let manifest = chunk_file_to_dir(&input_path, dir.path(), 1024)?;
+
+        // A reader handing out odd-sized pieces must not affect chunk boundaries.
+        struct Trickle<'a>(&'a [u8]);
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let n = buf.len().min(self.0.len()).min(333);
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+        verify_stream_against_manifest(&manifest, Trickle(&data))?;
+
+        let mut bad = data.clone();
+        bad[5 * 1024 + 17] ^= 0x01;
+        match verify_stream_against_manifest(&manifest, Trickle(&bad)) {
+            Err(StorageError::StreamMismatch { index, consumed }) => {
+                assert_eq!((index, consumed), (5, 6 * 1024));
+            }
+            other => panic!("expected StreamMismatch, got {:?}", other),
+        }
+
+        assert!(matches!(
+            verify_stream_against_manifest(&manifest, &data[..9_000]),
+            Err(StorageError::StreamMismatch { index: 8, .. })
+        ));
+        let mut long = data.clone();
+        long.push(0);
+        assert!(matches!(
+            verify_stream_against_manifest(&manifest, &long[..]),
+            Err(StorageError::StreamMismatch {
+                index: 10,
+                consumed: 10_001
+            })
+        ));
+
+        // Chunks larger than the read buffer are hashed across several reads.
+        let big_dir = tempdir().unwrap();
+        let big_path = big_dir.path().join("big.bin");
+        let big_data = sample_bytes(200_000);
+        fs::write(&big_path, &big_data)?;
+        let big = chunk_file_to_dir(&big_path, big_dir.path(), 1 << 20)?;
+        verify_stream_against_manifest(&big, Trickle(&big_data))?;
+        assert!(verify_stream_against_manifest(&big, &big_data[..199_999]).is_err());
+
+        // A huge declared length is not allocated up front; the short stream just fails.
+        let mut huge = manifest.clone();
+        huge.chunks[0].len = 1 << 40;
+        assert!(matches!(
+            verify_stream_against_manifest(&huge, &data[..]),
+            Err(StorageError::StreamMismatch {
+                index: 0,
+                consumed: 10_000
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn strict_verification_flags_leftover_chunks() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("foo.bin");
+        fs::write(&input_path, sample_bytes(3000))?;
+        let store = dir.path().join("store");
+        let manifest = chunk_file_to_dir(&input_path, &store, 1024)?;
+        verify_chunks_strict(&manifest, &store)?;
+
+        // Unrelated names and non-canonical widths are not chunk files of this manifest.
+        fs::write(store.join("other.bin.chunk.00000099"), b"x")?;
+        fs::write(store.join("foo.bin.chunk.000000099"), b"x")?;
+        fs::write(store.join("foo.bin.manifest"), b"x")?;
+        verify_chunks_strict(&manifest, &store)?;
+
+        fs::write(store.join("foo.bin.chunk.00000099"), b"stale")?;
+        fs::write(store.join("foo.bin.chunk.00000003"), b"stale")?;
+        verify_chunks(&manifest, &store)?;
+        assert!(matches!(
+            verify_chunks_strict(&manifest, &store),
+            Err(StorageError::UnexpectedChunk { index: 3 })
+        ));
+
+        assert_eq!(
+            chunk_index_from_filename("a.bin.00000007.dat", "a.bin", Some(".dat")),
+            Some(7)
+        );
+        assert_eq!(
+            chunk_index_from_filename("a.bin.chunk.-0000007", "a.bin", None),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn verify_and_reassemble_with_renamed_chunks() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("release.tar");
+        let data = sample_bytes(9000);
+        fs::write(&input_path, &data)?;
+
+        let store = dir.path().join("store");
+        let manifest = chunk_file_to_dir(&input_path, &store, 1024)?;
+        for idx in 0..manifest.chunks.len() {
+            fs::rename(
+                chunk_path(&store, "release.tar", idx),
+                chunk_path(&store, "cache-blob", idx),
+            )?;
+        }
+
+        // Default naming no longer finds the chunks.
+        assert!(matches!(
+            verify_chunks(&manifest, &store),
+            Err(StorageError::Io(_))
+        ));
+
+        verify_chunks_named(&manifest, &store, Some("cache-blob"))?;
+        let out_path = dir.path().join("rebuilt.tar");
+        reassemble_named(&manifest, &store, &out_path, Some("cache-blob"))?;
+        assert_eq!(fs::read(out_path)?, data);
+        Ok(())
+    }
+
+    #[test]
+    fn size_accumulation_overflow_is_an_error() {
+        // Running-total helper used by chunking and verification: must not silently cap.
+        let reported = [u64::MAX / 2, u64::MAX / 2, 2];
+        let mut total = 0u64;
+        let mut result = Ok(0);
+        for n in reported {
+            result = add_size(total, n);
+            match result {
+                Ok(t) => total = t,
+                Err(_) => break,
+            }
+        }
+        assert!(matches!(result, Err(StorageError::SizeOverflow)));
+        assert_eq!(add_size(u64::MAX - 1, 1).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn manifest_lengths_past_u64_max_overflow() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        fs::write(&input_path, sample_bytes(16 * 1024))?;
+        let mut manifest = chunk_file_cdc(&input_path, dir.path(), 512, 2048, 8192)?;
+        assert!(manifest.version >= 2 && manifest.chunks.len() >= 2);
+        manifest.chunks[0].len = u64::MAX;
+        manifest.chunks[1].len = 2;
+
+        // `h:<hex>:<len>` lines whose lengths wrap are an overflow, not a size mismatch.
+        let text = String::from_utf8(manifest.canonical_bytes()).unwrap();
+        assert!(text.contains(&format!(":{}\n", u64::MAX)));
+        assert!(matches!(
+            parse_manifest(&text, ManifestParseMode::Strict),
+            Err(StorageError::SizeOverflow)
+        ));
+        // Verification rejects it before reading chunk files.
+        assert!(matches!(
+            verify_chunks(&manifest, dir.path()),
+            Err(StorageError::SizeOverflow)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn verify_reports_lowest_corrupt_index() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        fs::write(&input_path, sample_bytes(64 * 1024))?;
+
+        let manifest = chunk_file_to_dir(&input_path, dir.path(), 1024)?;
+        for idx in [41, 7] {
+            let p = chunk_path(dir.path(), &manifest.file_name, idx);
+            let mut c = fs::read(&p)?;
+            c[3] ^= 0x55;
+            fs::write(&p, &c)?;
+        }
+
+        for _ in 0..20 {
+            match verify_chunks(&manifest, dir.path()) {
+                Err(StorageError::HashMismatch { index }) => assert_eq!(index, 7),
+                other => panic!("expected HashMismatch on chunk 7, got {:?}", other),
+            }
+        }
+        match verify_chunks_sequential(&manifest, dir.path()) {
+            Err(StorageError::HashMismatch { index }) => assert_eq!(index, 7),
+            other => panic!("expected HashMismatch on chunk 7, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    fn pubkey_of(secret: &[u8; 32]) -> PublicKey {
+        let sk = SecretKey::from_bytes(secret).unwrap();
+        DalekPublicKey::from(&sk).to_bytes()
+    }
+
+    #[test]
+    fn threshold_multi_signer_round_trip() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("artifact.bin");
+        fs::write(&input_path, sample_bytes(3000))?;
+        let mut manifest = chunk_file_to_dir(&input_path, dir.path(), 1024)?;
+        let unsigned = manifest.canonical_bytes();
+
+        let (k1, k2, k3) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        let allowed = [pubkey_of(&k1), pubkey_of(&k2), pubkey_of(&k3)];
+        sign_manifest_add(&mut manifest, &k2)?;
+        sign_manifest_add(&mut manifest, &k1)?;
+        sign_manifest_add(&mut manifest, &k1)?; // re-sign: no duplicate entry
+        assert_eq!(manifest.signers.len(), 2);
+        assert_eq!(manifest.canonical_bytes(), unsigned);
+
+        let mpath = manifest_path(dir.path(), &manifest.file_name);
+        write_manifest(&manifest, &mpath)?;
+        let loaded = read_manifest(&mpath)?;
+        assert_eq!(loaded.signers, manifest.signers);
+        verify_manifest_signature(&loaded)?;
+        verify_manifest_signatures(&loaded, 2, &allowed)?;
+        assert!(matches!(
+            verify_manifest_signatures(&loaded, 3, &allowed),
+            Err(StorageError::ThresholdNotMet {
+                valid: 2,
+                required: 3
+            })
+        ));
+        // Keys outside the allowed set do not count.
+        assert!(verify_manifest_signatures(&loaded, 2, &allowed[1..]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_single_signer_manifest_parses() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let mut manifest = Manifest {
+            version: 1,
+            file_name: "old.bin".into(),
+            total_size: 0,
+            chunk_size: 1024,
+            chunks: Vec::new(),
+            chunk_suffix: None,
+            encryption: None,
+            chunk_runs: false,
+            metadata: BTreeMap::new(),
+            parity: None,
+            signers: Vec::new(),
+        };
+        let secret = [5u8; 32];
+        sign_manifest_inplace(&mut manifest, &secret)?;
+        let (signer, sig) = manifest.signers[0];
+        let text = format!(
+            "{}signer:{}\nsignature:{}\n",
+            String::from_utf8(manifest.canonical_bytes()).unwrap(),
+            hex::encode(signer),
+            hex::encode(sig)
+        );
+        let mpath = dir.path().join("old.bin.manifest");
+        fs::write(&mpath, text)?;
+
+        let loaded = read_manifest(&mpath)?;
+        assert_eq!(loaded.signers, vec![(pubkey_of(&secret), sig)]);
+        verify_manifest_signatures(&loaded, 1, &[pubkey_of(&secret)])?;
+        Ok(())
+    }
+
+    #[test]
+    fn verifying_reader_reads_to_eof() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        let data = sample_bytes(10_000);
+        fs::write(&input_path, &data)?;
+        let manifest = chunk_file_to_dir(&input_path, dir.path(), 1024)?;
+
+        let mut out = Vec::new();
+        VerifyingChunkReader::new(&manifest, dir.path()).read_to_end(&mut out)?;
+        assert_eq!(out, data);
+
+        let mut via_writer = Vec::new();
+        reassemble_to_writer(&manifest, dir.path(), &mut via_writer)?;
+        assert_eq!(via_writer, data);
+        Ok(())
+    }
+
+    #[test]
+    fn verifying_reader_fails_at_corrupt_chunk() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        fs::write(&input_path, sample_bytes(10_000))?;
+        let manifest = chunk_file_to_dir(&input_path, dir.path(), 1024)?;
+        let p = chunk_path(dir.path(), &manifest.file_name, 3);
+        let mut c = fs::read(&p)?;
+        c[100] ^= 0x01;
+        fs::write(&p, &c)?;
+
+        let mut reader = VerifyingChunkReader::new(&manifest, dir.path());
+        let mut consumed = 0usize;
+        let mut buf = [0u8; 300];
+        let err = loop {
+            match reader.read(&mut buf) {
+                Ok(0) => panic!("reached EOF despite corrupt chunk"),
+                Ok(n) => consumed += n,
+                Err(e) => break e,
+            }
+        };
+        assert_eq!(
+            consumed,
+            3 * 1024,
+            "error must surface at the start of chunk 3"
+        );
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        let inner = err.get_ref().and_then(|e| e.downcast_ref::<StorageError>());
+        assert!(matches!(
+            inner,
+            Some(StorageError::HashMismatch { index: 3 })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn v2_chunk_hash_binds_length() -> Result<(), StorageError> {
+        let full = pseudo_random_bytes(4096, 11);
+        let short = &full[..1000];
+        assert_eq!(chunk_hash(1, &full), sha256_bytes(&full));
+        assert_ne!(chunk_hash(2, &full), sha256_bytes(&full));
+        assert_ne!(chunk_hash(2, short), chunk_hash(2, &full));
+        assert_ne!(chunk_hash(2, short), chunk_hash(1, short));
+
+        // CDC (v2) manifests carry and verify length-bound hashes.
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        let data = pseudo_random_bytes(40_000, 12);
+        fs::write(&input_path, &data)?;
+        let mut manifest = chunk_file_cdc(&input_path, dir.path(), 512, 2048, 8192)?;
+        let first = manifest.chunks[0].len as usize;
+        assert_eq!(manifest.chunks[0].hash, chunk_hash(2, &data[..first]));
+        verify_chunks(&manifest, dir.path())?;
+
+        // A plain SHA256 in a v2 manifest is rejected.
+        manifest.chunks[0].hash = sha256_bytes(&data[..first]);
+        assert!(matches!(
+            verify_chunks(&manifest, dir.path()),
+            Err(StorageError::HashMismatch { index: 0 })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn custom_chunk_suffix_round_trip() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        let data = sample_bytes(5000);
+        fs::write(&input_path, &data)?;
+        let store = dir.path().join("store");
+
+        for (sfx, first_name) in [
+            (".dat", "sample.bin.00000000.dat"),
+            ("", "sample.bin.00000000"),
+        ] {
+            let out = store.join(if sfx.is_empty() { "bare" } else { "dat" });
+            let manifest = chunk_file_to_dir_with_suffix(&input_path, &out, 2048, Some(sfx))?;
+            assert!(out.join(first_name).exists());
+            assert!(!chunk_path(&out, "sample.bin", 0).exists());
+            assert_eq!(manifest.chunk_path(&out, 0), out.join(first_name));
+
+            let mpath = manifest_path(&out, &manifest.file_name);
+            write_manifest(&manifest, &mpath)?;
+            let loaded = read_manifest(&mpath)?;
+            assert_eq!(loaded.chunk_suffix.as_deref(), Some(sfx));
+            verify_chunks(&loaded, &out)?;
+            let output = out.join("restored.bin");
+            reassemble(&loaded, &out, &output)?;
+            assert_eq!(fs::read(&output)?, data);
+        }
+
+        // Default naming leaves the canonical form (and thus old signatures) unchanged.
+        let plain = chunk_file_to_dir(&input_path, &store.join("plain"), 2048)?;
+        assert!(
+            !String::from_utf8(plain.canonical_bytes())
+                .unwrap()
+                .contains("chunk_suffix")
+        );
+        assert!(chunk_file_to_dir_with_suffix(&input_path, &store, 2048, Some("/../x")).is_err());
+        for sfx in ["bin ", ".dat\t"] {
+            assert!(chunk_file_to_dir_with_suffix(&input_path, &store, 2048, Some(sfx)).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn relaxed_parse_skips_experimental_lines() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        fs::write(&input_path, sample_bytes(3000))?;
+        let mut manifest = chunk_file_to_dir(&input_path, dir.path(), 1024)?;
+        sign_manifest_inplace(&mut manifest, &[8u8; 32])?;
+        let text = manifest.to_string_with_signature();
+        let (head, tail) = text.split_at(text.find("chunks:").unwrap());
+        let extended = format!("{}x-custom:some value\n{}", head, tail);
+
+        assert!(matches!(
+            parse_manifest(&extended, ManifestParseMode::Strict),
+            Err(StorageError::InvalidManifest(_))
+        ));
+        let relaxed = parse_manifest(&extended, ManifestParseMode::Relaxed)?;
+        assert_eq!(relaxed.canonical_bytes(), manifest.canonical_bytes());
+        verify_manifest_signature(&relaxed)?;
+
+        for bad in ["x-:novalue\n", "x-custom\n", "custom:1\n", "x-bad name:1\n"] {
+            let text = format!("{}{}{}", head, bad, tail);
+            assert!(
+                parse_manifest(&text, ManifestParseMode::Relaxed).is_err(),
+                "{:?} must be rejected",
+                bad
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn reproducible_manifest_is_byte_identical() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let data = sample_bytes(4000);
+        let mut outputs = Vec::new();
+        for (run, keys) in [[[1u8; 32], [2u8; 32]], [[2u8; 32], [1u8; 32]]]
+            .iter()
+            .enumerate()
+        {
+            let run_dir = dir.path().join(format!("run{}", run));
+            fs::create_dir(&run_dir)?;
+            let input_path = run_dir.join("sample.bin");
+            fs::write(&input_path, &data)?;
+            let mut manifest = chunk_file_to_dir(&input_path, &run_dir.join("out"), 1024)?;
+            for key in keys {
+                sign_manifest_add(&mut manifest, key)?;
+            }
+            let path = run_dir.join("sample.manifest");
+            write_manifest_reproducible(&manifest, &path)?;
+            outputs.push((fs::read(&path)?, manifest.to_string_with_signature()));
+        }
+        assert_eq!(outputs[0].0, outputs[1].0);
+        // Insertion order still shows in the plain form.
+        assert_ne!(outputs[0].1, outputs[1].1);
+
+        let parsed = parse_manifest(
+            std::str::from_utf8(&outputs[0].0).unwrap(),
+            ManifestParseMode::Strict,
+        )?;
+        let allowed = [pubkey_of(&[1u8; 32]), pubkey_of(&[2u8; 32])];
+        verify_manifest_signatures(&parsed, 2, &allowed)?;
+        assert_eq!(
+            parsed.to_reproducible_string().as_bytes(),
+            &outputs[0].0[..]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn crlf_manifest_parses_like_lf() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        fs::write(&input_path, sample_bytes(3000))?;
+        let mut manifest = chunk_file_to_dir(&input_path, dir.path(), 1024)?;
+        sign_manifest_inplace(&mut manifest, &[8u8; 32])?;
+        let lf = manifest.to_string_with_signature();
+        let crlf = lf.replace('\n', "\r\n");
+        // A lone '\r' on the last line and trailing blanks are tolerated too.
+        let ragged = format!("{}\r", lf.trim_end().replace('\n', " \t\n"));
+
+        let from_lf = parse_manifest(&lf, ManifestParseMode::Strict)?;
+        for text in [crlf, ragged] {
+            let parsed = parse_manifest(&text, ManifestParseMode::Strict)?;
+            assert_eq!(parsed.file_name, from_lf.file_name);
+            assert_eq!(parsed.canonical_bytes(), from_lf.canonical_bytes());
+            assert_eq!(parsed.signers, from_lf.signers);
+            verify_manifest_signature(&parsed)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn merkle_root_v2_resists_duplication_and_node_as_leaf() {
+        let [a, b, c, d] = [[1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32]];
+
+        // v1: duplicating the odd last leaf collides, and so does presenting an internal
+        // node (here the root itself) as a one-leaf set.
+        assert_eq!(merkle_root(&[a, b, c]), merkle_root(&[a, b, c, c]));
+        let root = merkle_root(&[a, b, c, d]).unwrap();
+        assert_eq!(merkle_root(&[root]), Some(root));
+
+        // v2: neither trick works.
+        assert_ne!(merkle_root_v2(&[a, b, c]), merkle_root_v2(&[a, b, c, c]));
+        let root_v2 = merkle_root_v2(&[a, b, c, d]).unwrap();
+        assert_ne!(merkle_root_v2(&[root_v2]), Some(root_v2));
+        assert_ne!(merkle_root_v2(&[a]), Some(a));
+        assert_eq!(merkle_root_v2(&[]), None);
+        assert_eq!(merkle_root_v2(&[c, a, b]), merkle_root_v2(&[a, b, c]));
+    }
+
+    #[test]
+    fn chunks_for_range_fixed_and_cdc() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        fs::write(&input_path, sample_bytes(2500))?;
+        // Chunks: [0,1000) [1000,2000) [2000,2500)
+        let fixed = chunk_file_to_dir(&input_path, dir.path(), 1000)?;
+
+        assert_eq!(chunks_for_range(&fixed, 10, 20), vec![0]);
+        assert_eq!(chunks_for_range(&fixed, 1000, 2000), vec![1]);
+        assert_eq!(chunks_for_range(&fixed, 999, 1001), vec![0, 1]);
+        assert_eq!(chunks_for_range(&fixed, 500, 2400), vec![0, 1, 2]);
+        assert_eq!(chunks_for_range(&fixed, 2100, u64::MAX), vec![2]);
+        assert_eq!(chunks_for_range(&fixed, 2500, 3000), Vec::<usize>::new());
+        assert_eq!(chunks_for_range(&fixed, 30, 30), Vec::<usize>::new());
+
+        let data = pseudo_random_bytes(64 * 1024, 7);
+        fs::write(&input_path, &data)?;
+        let cdc_dir = dir.path().join("cdc");
+        fs::create_dir(&cdc_dir)?;
+        let cdc = chunk_file_cdc(&input_path, &cdc_dir, 2048, 8192, 32768)?;
+        assert!(cdc.chunks.len() > 2);
+        let first = cdc.chunks[0].len;
+        assert_eq!(chunks_for_range(&cdc, first - 1, first + 1), vec![0, 1]);
+        assert_eq!(
+            chunks_for_range(&cdc, 0, u64::MAX),
+            (0..cdc.chunks.len()).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn verify_against_sha256sums() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        fs::write(&input_path, sample_bytes(5000))?;
+        let store = dir.path().join("store");
+        fs::create_dir(&store)?;
+        let manifest = chunk_file_to_dir(&input_path, &store, 1024)?;
+
+        let sums: String = (0..manifest.chunks.len())
+            .map(|i| {
+                let path = manifest.chunk_path(&store, i);
+                let digest: Hash = Sha256::digest(fs::read(&path).unwrap()).into();
+                let name = path.file_name().unwrap().to_str().unwrap().to_string();
+                // Mix text and binary mode markers, as sha256sum output may.
+                let sep = if i % 2 == 0 { " " } else { "*" };
+                format!("{} {}{}\n", hex::encode(digest), sep, name)
+            })
+            .collect();
+        let sums_path = dir.path().join("SHA256SUMS");
+        fs::write(&sums_path, &sums)?;
+        assert_eq!(
+            verify_chunks_against_sums(&store, &sums_path)?,
+            manifest.chunks.len()
+        );
+
+        let victim = manifest.chunk_path(&store, 2);
+        let mut bytes = fs::read(&victim)?;
+        bytes[0] ^= 0xff;
+        fs::write(&victim, bytes)?;
+        match verify_chunks_against_sums(&store, &sums_path) {
+            Err(StorageError::ChecksumMismatch { file }) => {
+                assert_eq!(Path::new(&file), victim.strip_prefix(&store).unwrap())
+            }
+            other => panic!("expected checksum mismatch, got {:?}", other),
+        }
+
+        fs::write(&sums_path, format!("{}  ../sample.bin\n", "00".repeat(32)))?;
+        assert!(matches!(
+            verify_chunks_against_sums(&store, &sums_path),
+            Err(StorageError::InvalidSums { line: 1, .. })
+        ));
+        Ok(())
+    }
+
+    fn pseudo_random_bytes(len: usize, seed: u64) -> Vec<u8> {
+        let mut x = seed;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                (x >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cdc_dedups_after_prepend() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let data = pseudo_random_bytes(512 * 1024, 0x5eed);
+        let a_path = dir.path().join("a.bin");
+        fs::write(&a_path, &data)?;
+        let mut shifted = vec![0x42u8];
+        shifted.extend_from_slice(&data);
+        let b_path = dir.path().join("b.bin");
+        fs::write(&b_path, &shifted)?;
+
+        let a_dir = dir.path().join("a");
+        let b_dir = dir.path().join("b");
+        let ma = chunk_file_cdc(&a_path, &a_dir, 2048, 8192, 32768)?;
+        let mb = chunk_file_cdc(&b_path, &b_dir, 2048, 8192, 32768)?;
+        assert_eq!(ma.version, 2);
+        assert!(ma.chunks.len() > 10, "expected many chunks");
+
+        let a_hashes: std::collections::HashSet<_> = ma.chunks.iter().map(|c| c.hash).collect();
+        let shared = mb
+            .chunks
+            .iter()
+            .filter(|c| a_hashes.contains(&c.hash))
+            .count();
+        assert!(
+            shared * 2 > mb.chunks.len(),
+            "only {} of {} chunks shared",
+            shared,
+            mb.chunks.len()
+        );
+
+        // v2 manifest survives a write/read cycle and reassembles byte-exact.
+        let mpath = manifest_path(&b_dir, &mb.file_name);
+        write_manifest(&mb, &mpath)?;
+        let loaded = read_manifest(&mpath)?;
+        assert_eq!(loaded.version, 2);
+        assert_eq!(loaded.hash(), mb.hash());
+        verify_chunks(&loaded, &b_dir)?;
+        let out_path = dir.path().join("rebuilt.bin");
+        reassemble(&loaded, &b_dir, &out_path)?;
+        assert_eq!(fs::read(out_path)?, shifted);
+
+        // Oversized `max` is refused up front instead of aborting on allocation.
+        for max in [MAX_CDC_CHUNK + 1, usize::MAX] {
+            assert!(matches!(
+                chunk_file_cdc(&a_path, &a_dir, 2048, 8192, max),
+                Err(StorageError::InvalidManifest(_))
+            ));
+        }
+        Ok(())
+    }
+
     #[test]
     fn detect_corrupt_chunk() -> Result<(), StorageError> {
         let dir = tempdir().unwrap();
@@ -0,0 +1,155 @@
+//! Tokio counterparts of `chunk_file_to_dir` and `reassemble` (feature `async`).
+//! IO goes through `tokio::fs` / `tokio::io`; hashing stays synchronous per chunk. Manifests
+//! and output are byte-identical to the blocking versions for the same input.
+
+use super::{
+    ChunkMeta, Manifest, StorageError, add_size, chunk_hash, chunk_path_with_suffix, open_chunk,
+};
+use std::path::Path;
+use tokio::fs::{self, File};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+/// Async `read_full`: fill `buf` unless EOF comes first, so chunk boundaries do not depend
+/// on how reads are split.
+async fn read_full<R: AsyncRead + Unpin>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]).await {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Async `storage::chunk_file_to_dir`.
+pub async fn chunk_file_to_dir(
+    input: &Path,
+    out_dir: &Path,
+    chunk_size: usize,
+) -> Result<Manifest, StorageError> {
+    if chunk_size == 0 {
+        return Err(StorageError::InvalidManifest(
+            "chunk_size must be > 0".into(),
+        ));
+    }
+    fs::create_dir_all(out_dir).await?;
+    let file_name = input
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| StorageError::InvalidManifest("invalid file name".into()))?
+        .to_string();
+
+    let mut f = File::open(input).await?;
+    let mut buf = vec![0u8; chunk_size];
+    let mut chunks = Vec::new();
+    let mut total: u64 = 0;
+    loop {
+        let n = read_full(&mut f, &mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        total = add_size(total, n as u64)?;
+        let data = &buf[..n];
+        let hash = chunk_hash(1, data);
+        fs::write(
+            chunk_path_with_suffix(out_dir, &file_name, None, chunks.len()),
+            data,
+        )
+        .await?;
+        chunks.push(ChunkMeta {
+            hash,
+            len: n as u64,
+        });
+    }
+
+    Ok(Manifest {
+        version: 1,
+        file_name,
+        total_size: total,
+        chunk_size: chunk_size as u64,
+        chunks,
+        chunk_suffix: None,
+        encryption: None,
+        chunk_runs: false,
+        metadata: Default::default(),
+        parity: None,
+        signers: Vec::new(),
+    })
+}
+
+/// Async `storage::reassemble`: each chunk is read and checked against its manifest hash
+/// before it is written, so a corrupt chunk stops the output at the preceding chunk.
+pub async fn reassemble(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    output: &Path,
+) -> Result<(), StorageError> {
+    let mut out = File::create(output).await?;
+    for idx in 0..manifest.chunks.len() {
+        let data = fs::read(chunk_path_with_suffix(
+            chunk_dir,
+            &manifest.file_name,
+            manifest.chunk_suffix.as_deref(),
+            idx,
+        ))
+        .await?;
+        let data = open_chunk(manifest, idx, None, data)?;
+        if chunk_hash(manifest.version, &data) != manifest.chunks[idx].hash {
+            return Err(StorageError::HashMismatch { index: idx });
+        }
+        out.write_all(&data).await?;
+    }
+    // tokio::fs::File finishes writes in the background; flush before reporting success.
+    out.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{
+        manifest_path, read_manifest, sign_manifest_inplace, verify_chunks,
+        verify_manifest_signature, write_manifest,
+    };
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn chunk_sign_verify_round_trip() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("sample.bin");
+        let data: Vec<u8> = (0..32 * 1024 + 123).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&input_path, &data)?;
+
+        let mut manifest = chunk_file_to_dir(&input_path, dir.path(), 1024).await?;
+        let sync_dir = dir.path().join("sync");
+        let sync_manifest = crate::storage::chunk_file_to_dir(&input_path, &sync_dir, 1024)?;
+        assert_eq!(
+            manifest.to_string_with_signature(),
+            sync_manifest.to_string_with_signature()
+        );
+
+        let secret = [7u8; 32];
+        sign_manifest_inplace(&mut manifest, &secret)?;
+        let mpath = manifest_path(dir.path(), &manifest.file_name);
+        write_manifest(&manifest, &mpath)?;
+
+        let loaded = read_manifest(&mpath)?;
+        verify_manifest_signature(&loaded)?;
+        verify_chunks(&loaded, dir.path())?;
+
+        let out_path = dir.path().join("rebuilt.bin");
+        reassemble(&loaded, dir.path(), &out_path).await?;
+        assert_eq!(std::fs::read(&out_path)?, data);
+
+        let chunk0 = crate::storage::chunk_path(dir.path(), &loaded.file_name, 0);
+        std::fs::write(&chunk0, b"corrupt")?;
+        assert!(matches!(
+            reassemble(&loaded, dir.path(), &out_path).await,
+            Err(StorageError::HashMismatch { index: 0 })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+//! JSON form of `Manifest` (feature `json`) for tooling that would rather not parse the
+//! line format. It is only an alternate encoding: `from_json` accepts exactly the manifests
+//! the text format can express, and a round trip preserves every field, so
+//! `canonical_bytes` and hence signature checks are unchanged.
+
+use super::{
+    ChunkEncryption, ChunkMeta, Manifest, ManifestParseMode, ParityInfo, StorageError,
+    hex_to_array, parse_manifest,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestJson {
+    version: u8,
+    file_name: String,
+    total_size: u64,
+    chunk_size: u64,
+    #[serde(default)]
+    chunk_suffix: Option<String>,
+    #[serde(default)]
+    encryption: Option<String>,
+    #[serde(default)]
+    chunk_runs: bool,
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+    chunks: Vec<ChunkJson>,
+    #[serde(default)]
+    parity: Option<ParityJson>,
+    #[serde(default)]
+    signers: Vec<SignerJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChunkJson {
+    /// Hex SHA256.
+    hash: String,
+    len: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ParityJson {
+    data_shards: usize,
+    /// Hex `chunk_hash` of each parity chunk.
+    hashes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SignerJson {
+    /// Hex ed25519 public key.
+    signer: String,
+    /// Hex ed25519 signature over `canonical_bytes`.
+    signature: String,
+}
+
+impl Manifest {
+    /// Pretty-printed JSON; hashes, keys and signatures are lowercase hex.
+    pub fn to_json(&self) -> String {
+        let doc = ManifestJson {
+            version: self.version,
+            file_name: self.file_name.clone(),
+            total_size: self.total_size,
+            chunk_size: self.chunk_size,
+            chunk_suffix: self.chunk_suffix.clone(),
+            encryption: self.encryption.map(|e| e.as_str().to_string()),
+            chunk_runs: self.chunk_runs,
+            metadata: self.metadata.clone(),
+            chunks: self
+                .chunks
+                .iter()
+                .map(|c| ChunkJson {
+                    hash: hex::encode(c.hash),
+                    len: c.len,
+                })
+                .collect(),
+            parity: self.parity.as_ref().map(|p| ParityJson {
+                data_shards: p.data_shards,
+                hashes: p.hashes.iter().map(hex::encode).collect(),
+            }),
+            signers: self
+                .signers
+                .iter()
+                .map(|(signer, sig)| SignerJson {
+                    signer: hex::encode(signer),
+                    signature: hex::encode(sig),
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&doc).expect("manifest JSON serialization cannot fail")
+    }
+
+    /// Inverse of `to_json`. Rejects anything `parse_manifest` (strict) would reject for the
+    /// equivalent text manifest, including v1 chunk lengths that disagree with chunk_size.
+    pub fn from_json(s: &str) -> Result<Manifest, StorageError> {
+        let doc: ManifestJson = serde_json::from_str(s)
+            .map_err(|e| StorageError::InvalidManifest(format!("bad manifest JSON: {}", e)))?;
+        let encryption = match doc.encryption.as_deref() {
+            None => None,
+            Some("xchacha20poly1305") => Some(ChunkEncryption::XChaCha20Poly1305),
+            Some(_) => return Err(StorageError::InvalidManifest("unknown encryption".into())),
+        };
+        let chunks = doc
+            .chunks
+            .iter()
+            .map(|c| {
+                Ok(ChunkMeta {
+                    hash: hex_to_array::<32>(&c.hash)?,
+                    len: c.len,
+                })
+            })
+            .collect::<Result<Vec<_>, StorageError>>()?;
+        let parity = match &doc.parity {
+            None => None,
+            Some(p) => Some(ParityInfo {
+                data_shards: p.data_shards,
+                hashes: p
+                    .hashes
+                    .iter()
+                    .map(|h| hex_to_array::<32>(h))
+                    .collect::<Result<_, _>>()?,
+            }),
+        };
+        let signers = doc
+            .signers
+            .iter()
+            .map(|s| {
+                Ok((
+                    hex_to_array::<32>(&s.signer)?,
+                    hex_to_array::<64>(&s.signature)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, StorageError>>()?;
+        let manifest = Manifest {
+            version: doc.version,
+            file_name: doc.file_name,
+            total_size: doc.total_size,
+            chunk_size: doc.chunk_size,
+            chunks,
+            chunk_suffix: doc.chunk_suffix,
+            encryption,
+            chunk_runs: doc.chunk_runs,
+            metadata: doc.metadata,
+            parity,
+            signers,
+        };
+
+        // Route validation through the text parser so both encodings accept the same set of
+        // manifests; a field that would not survive the line format is an error, not a
+        // silently different signing input.
+        let text = parse_manifest(
+            &manifest.to_string_with_signature(),
+            ManifestParseMode::Strict,
+        )?;
+        let lens_match = text.chunks.len() == manifest.chunks.len()
+            && text
+                .chunks
+                .iter()
+                .zip(&manifest.chunks)
+                .all(|(a, b)| a.len == b.len);
+        if text.version != manifest.version
+            || text.file_name != manifest.file_name
+            || text.chunk_suffix != manifest.chunk_suffix
+            || text.metadata != manifest.metadata
+            || !lens_match
+        {
+            return Err(StorageError::InvalidManifest(
+                "manifest JSON not representable in the line format".into(),
+            ));
+        }
+        Ok(manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{chunk_file_to_dir, sign_manifest_add, verify_manifest_signatures};
+    use super::*;
+    use ed25519_dalek::{PublicKey as DalekPublicKey, SecretKey};
+
+    fn public(secret: &[u8; 32]) -> [u8; 32] {
+        let sk = SecretKey::from_bytes(secret).unwrap();
+        DalekPublicKey::from(&sk).to_bytes()
+    }
+
+    #[test]
+    fn json_round_trip_keeps_signatures_valid() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("data.bin");
+        let mut data = vec![0u8; 4096];
+        data.extend((0..3000u32).map(|i| (i % 251) as u8));
+        std::fs::write(&input, &data).unwrap();
+
+        let mut manifest = chunk_file_to_dir(&input, &dir.path().join("out"), 1024)
+            .unwrap()
+            .with_chunk_runs();
+        manifest
+            .set_metadata("mime", "application/octet-stream")
+            .unwrap();
+        let (a, b) = ([5u8; 32], [6u8; 32]);
+        sign_manifest_add(&mut manifest, &a).unwrap();
+        sign_manifest_add(&mut manifest, &b).unwrap();
+
+        let json = manifest.to_json();
+        let back = Manifest::from_json(&json).unwrap();
+        assert_eq!(back.canonical_bytes(), manifest.canonical_bytes());
+        assert_eq!(
+            back.to_string_with_signature(),
+            manifest.to_string_with_signature()
+        );
+        assert!(back.chunk_runs);
+        assert_eq!(back.to_json(), json);
+        assert!(verify_manifest_signatures(&back, 2, &[public(&a), public(&b)]).is_ok());
+
+        // Tampering in JSON is caught by the same signature check as in text.
+        let tampered = json.replacen("application/octet-stream", "text/plain", 1);
+        let tampered = Manifest::from_json(&tampered).unwrap();
+        assert!(verify_manifest_signatures(&tampered, 1, &[public(&a)]).is_err());
+    }
+
+    #[test]
+    fn from_json_rejects_what_text_cannot_express() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("f");
+        std::fs::write(&input, b"hello world").unwrap();
+        let manifest = chunk_file_to_dir(&input, &dir.path().join("out"), 4).unwrap();
+        let json = manifest.to_json();
+
+        let newline_name = json.replacen("\"file_name\": \"f\"", "\"file_name\": \"f\\nx\"", 1);
+        assert!(Manifest::from_json(&newline_name).is_err());
+        let unknown_field = json.replacen("{", "{\"extra\": 1,", 1);
+        assert!(Manifest::from_json(&unknown_field).is_err());
+        let short_hash = json.replacen(&hex::encode(manifest.chunks[0].hash), "abcd", 1);
+        assert!(Manifest::from_json(&short_hash).is_err());
+        assert!(Manifest::from_json("not json").is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! Reed–Solomon parity chunks (feature `parity`). `add_parity` encodes `m` parity shards
+//! over a manifest's `k` data chunks; `reassemble_with_repair` rebuilds up to `m` missing or
+//! corrupt data chunks while writing the output. Both hold all shards in memory, so they
+//! suit files up to a few hundred MiB.
+
+use super::{
+    MAX_PARITY_SHARDS, Manifest, ParityInfo, StorageError, chunk_file_to_dir, chunk_hash,
+    read_verified_chunk,
+};
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+/// Shard length: the longest data chunk; shorter chunks are zero-padded to it.
+fn shard_len(manifest: &Manifest) -> usize {
+    manifest.chunks.iter().map(|c| c.len).max().unwrap_or(0) as usize
+}
+
+fn codec(data: usize, parity: usize) -> Result<ReedSolomon, StorageError> {
+    ReedSolomon::new(data, parity)
+        .map_err(|e| StorageError::InvalidManifest(format!("parity: {:?}", e)))
+}
+
+/// `chunk_file_to_dir` followed by `add_parity`.
+pub fn chunk_file_to_dir_with_parity(
+    input: &Path,
+    out_dir: &Path,
+    chunk_size: usize,
+    parity_count: usize,
+) -> Result<Manifest, StorageError> {
+    let mut manifest = chunk_file_to_dir(input, out_dir, chunk_size)?;
+    add_parity(&mut manifest, out_dir, parity_count)?;
+    Ok(manifest)
+}
+
+/// Read and verify the manifest's data chunks from `chunk_dir`, write `parity_count` parity
+/// chunks next to them (`Manifest::parity_path`) and record them in `manifest.parity`,
+/// replacing any previous parity. This changes the canonical bytes, so sign afterwards.
+/// Encrypted manifests are rejected: repair would need the key.
+pub fn add_parity(
+    manifest: &mut Manifest,
+    chunk_dir: &Path,
+    parity_count: usize,
+) -> Result<(), StorageError> {
+    let k = manifest.chunks.len();
+    if parity_count == 0 {
+        return Err(StorageError::InvalidManifest(
+            "parity_count must be > 0".into(),
+        ));
+    }
+    if k == 0 {
+        return Err(StorageError::InvalidManifest(
+            "cannot add parity to a manifest without chunks".into(),
+        ));
+    }
+    if k.saturating_add(parity_count) > MAX_PARITY_SHARDS {
+        return Err(StorageError::InvalidManifest(format!(
+            "{} chunks + {} parity exceeds {} shards",
+            k, parity_count, MAX_PARITY_SHARDS
+        )));
+    }
+    if manifest.encryption.is_some() {
+        return Err(StorageError::InvalidManifest(
+            "parity over encrypted chunks is not supported".into(),
+        ));
+    }
+
+    let len = shard_len(manifest);
+    let mut shards = Vec::with_capacity(k + parity_count);
+    for idx in 0..k {
+        let mut data = read_verified_chunk(manifest, chunk_dir, &manifest.file_name, idx, None)?;
+        data.resize(len, 0);
+        shards.push(data);
+    }
+    shards.resize(k + parity_count, vec![0u8; len]);
+    codec(k, parity_count)?
+        .encode(&mut shards)
+        .map_err(|e| StorageError::InvalidManifest(format!("parity: {:?}", e)))?;
+
+    let mut hashes = Vec::with_capacity(parity_count);
+    for (i, shard) in shards[k..].iter().enumerate() {
+        fs::write(manifest.parity_path(chunk_dir, i), shard)?;
+        hashes.push(chunk_hash(manifest.version, shard));
+    }
+    manifest.parity = Some(ParityInfo {
+        data_shards: k,
+        hashes,
+    });
+    Ok(())
+}
+
+/// `reassemble`, except that missing or corrupt data chunks are rebuilt from the parity
+/// chunks. Fails with `Unrecoverable` (before creating `output`) when more data chunks are
+/// lost than there are intact parity chunks. Chunk files on disk are left untouched.
+pub fn reassemble_with_repair(
+    manifest: &Manifest,
+    chunk_dir: &Path,
+    output: &Path,
+) -> Result<(), StorageError> {
+    let k = manifest.chunks.len();
+    let mut shards: Vec<Option<Vec<u8>>> = Vec::with_capacity(k);
+    for idx in 0..k {
+        match read_verified_chunk(manifest, chunk_dir, &manifest.file_name, idx, None) {
+            Ok(data) => shards.push(Some(data)),
+            Err(StorageError::Io(_)) | Err(StorageError::HashMismatch { .. }) => shards.push(None),
+            Err(e) => return Err(e),
+        }
+    }
+
+    let lost = shards.iter().filter(|s| s.is_none()).count();
+    if lost > 0 {
+        let Some(parity) = &manifest.parity else {
+            return Err(StorageError::Unrecoverable { lost, parity: 0 });
+        };
+        let len = shard_len(manifest);
+        for shard in shards.iter_mut().flatten() {
+            shard.resize(len, 0);
+        }
+        for (i, expected) in parity.hashes.iter().enumerate() {
+            let shard = fs::read(manifest.parity_path(chunk_dir, i))
+                .ok()
+                .filter(|s| s.len() == len && chunk_hash(manifest.version, s) == *expected);
+            shards.push(shard);
+        }
+        let intact = shards[k..].iter().filter(|s| s.is_some()).count();
+        if lost > intact {
+            return Err(StorageError::Unrecoverable {
+                lost,
+                parity: intact,
+            });
+        }
+        codec(k, parity.hashes.len())?
+            .reconstruct_data(&mut shards)
+            .map_err(|_| StorageError::Unrecoverable {
+                lost,
+                parity: intact,
+            })?;
+        shards.truncate(k);
+        for (idx, shard) in shards.iter_mut().enumerate() {
+            let shard = shard
+                .as_mut()
+                .expect("reconstruct_data fills every data shard");
+            shard.truncate(manifest.chunks[idx].len as usize);
+            if chunk_hash(manifest.version, shard) != manifest.chunks[idx].hash {
+                return Err(StorageError::HashMismatch { index: idx });
+            }
+        }
+    }
+
+    let mut out = File::create(output)?;
+    for shard in shards.iter().flatten() {
+        out.write_all(shard)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{ManifestParseMode, parse_manifest};
+    use super::*;
+
+    fn sample(dir: &Path, parity: usize) -> (Vec<u8>, Manifest) {
+        let input = dir.join("data.bin");
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        fs::write(&input, &data).unwrap();
+        let manifest = chunk_file_to_dir_with_parity(&input, dir, 1024, parity).unwrap();
+        (data, manifest)
+    }
+
+    #[test]
+    fn repair_rebuilds_deleted_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let (data, manifest) = sample(dir.path(), 2);
+        assert_eq!(manifest.parity.as_ref().unwrap().hashes.len(), 2);
+
+        // The parity lines are part of the signed text and survive a parse.
+        let text = manifest.to_string_with_signature();
+        assert!(text.contains("parity:10:2\n"));
+        let parsed = parse_manifest(&text, ManifestParseMode::Strict).unwrap();
+        assert_eq!(parsed.parity, manifest.parity);
+
+        fs::remove_file(manifest.chunk_path(dir.path(), 3)).unwrap();
+        // Corrupt the short last chunk too: two losses, two parity chunks.
+        fs::write(manifest.chunk_path(dir.path(), 9), b"garbage").unwrap();
+        let out = dir.path().join("out.bin");
+        reassemble_with_repair(&parsed, dir.path(), &out).unwrap();
+        assert_eq!(fs::read(&out).unwrap(), data);
+    }
+
+    #[test]
+    fn repair_fails_cleanly_beyond_parity() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, manifest) = sample(dir.path(), 1);
+        fs::remove_file(manifest.chunk_path(dir.path(), 0)).unwrap();
+        fs::remove_file(manifest.chunk_path(dir.path(), 5)).unwrap();
+        let out = dir.path().join("out.bin");
+        assert!(matches!(
+            reassemble_with_repair(&manifest, dir.path(), &out),
+            Err(StorageError::Unrecoverable { lost: 2, parity: 1 })
+        ));
+        assert!(!out.exists());
+
+        // A lost parity chunk counts against what can be rebuilt.
+        let dir = tempfile::tempdir().unwrap();
+        let (_, manifest) = sample(dir.path(), 1);
+        fs::remove_file(manifest.chunk_path(dir.path(), 2)).unwrap();
+        fs::remove_file(manifest.parity_path(dir.path(), 0)).unwrap();
+        assert!(matches!(
+            reassemble_with_repair(&manifest, dir.path(), &out),
+            Err(StorageError::Unrecoverable { lost: 1, parity: 0 })
+        ));
+    }
+
+    #[test]
+    fn parity_line_rejects_mismatched_counts() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, manifest) = sample(dir.path(), 1);
+        let text = manifest.to_string_with_signature();
+        let bad_k = text.replace("parity:10:1\n", "parity:9:1\n");
+        assert!(parse_manifest(&bad_k, ManifestParseMode::Strict).is_err());
+        let missing_hash = text.replace("parity:10:1\n", "parity:10:2\n");
+        assert!(parse_manifest(&missing_hash, ManifestParseMode::Strict).is_err());
+        assert!(add_parity(&mut manifest.clone(), dir.path(), 250).is_err());
+    }
+}
@@ -1,11 +1,14 @@
 //! Deterministic trace checker (non-ZK) for merged_trace.json artifacts.
 use crate::event::{Event, Hash, PublicKey, Signature};
 use crate::ledger::{Ledger, ZERO_HASH};
-use crate::scoring::{SybilConfig, SybilOverlay};
+use crate::scoring::{EquivocationPolicy, SybilConfig, SybilOverlay};
 use crate::validation::{ValidationContext, validate_event};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::io::BufRead;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TraceHeader {
     pub protocol_version: u8,
     pub max_backward_skew: u64,
@@ -16,12 +19,23 @@ pub struct TraceHeader {
     pub sources: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SybilConfigSerde {
     pub warmup_ticks: u64,
     pub quarantine_ticks: u64,
     pub fixed_point_scale: u64,
     pub max_link_walk: usize,
+    /// Equivocation policy; traces without the field use `quarantine`.
+    #[serde(default)]
+    pub policy: PolicySerde,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicySerde {
+    #[default]
+    Quarantine,
+    Slash,
 }
 
 impl From<SybilConfigSerde> for SybilConfig {
@@ -29,14 +43,17 @@ impl From<SybilConfigSerde> for SybilConfig {
         SybilConfig {
             warmup_ticks: s.warmup_ticks,
             quarantine_ticks: s.quarantine_ticks,
-            policy: crate::scoring::EquivocationPolicy::Quarantine,
+            policy: match s.policy {
+                PolicySerde::Quarantine => EquivocationPolicy::Quarantine,
+                PolicySerde::Slash => EquivocationPolicy::Slash,
+            },
             fixed_point_scale: s.fixed_point_scale,
             max_link_walk: s.max_link_walk,
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TraceRowSerde {
     pub node_id: u32,
     pub row_index: usize,
@@ -56,12 +73,169 @@ pub struct TraceRowSerde {
     pub author_weight_fp: u64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TraceDoc {
     pub header: TraceHeader,
     pub rows: Vec<TraceRowSerde>,
 }
 
+/// `sources` must be non-empty, sorted, and free of duplicates so merged artifacts are
+/// reproducible regardless of the order per-node traces were collected in.
+pub fn validate_sources(header: &TraceHeader) -> Result<(), String> {
+    if header.sources.is_empty() {
+        return Err("header sources is empty".into());
+    }
+    if let Some(i) = header.sources.windows(2).position(|w| w[0] >= w[1]) {
+        return Err(format!(
+            "header sources not sorted/deduplicated at {:?} >= {:?}",
+            header.sources[i],
+            header.sources[i + 1]
+        ));
+    }
+    Ok(())
+}
+
+/// Merge per-node traces into a single merged trace. All headers must agree on protocol
+/// and sybil parameters; rows are interleaved by (timestamp, node_id, row_index), sources
+/// are the sorted union of the inputs', and `final_merkle_root` is the last non-null row root.
+pub fn merge_traces(docs: &[TraceDoc]) -> Result<TraceDoc, String> {
+    let first = docs.first().ok_or("no traces to merge")?;
+    let mut sources = Vec::new();
+    let mut rows = Vec::new();
+    for (i, doc) in docs.iter().enumerate() {
+        let (a, b) = (&first.header, &doc.header);
+        if a.protocol_version != b.protocol_version
+            || a.max_backward_skew != b.max_backward_skew
+            || a.max_pending_total != b.max_pending_total
+            || a.max_drain_steps != b.max_drain_steps
+            || a.sybil_config != b.sybil_config
+        {
+            return Err(format!("trace {} header parameters differ from trace 0", i));
+        }
+        sources.extend(b.sources.iter().cloned());
+        rows.extend(doc.rows.iter().cloned());
+    }
+    sources.sort();
+    sources.dedup();
+    rows.sort_by_key(|r| (r.timestamp, r.node_id, r.row_index));
+
+    let header = TraceHeader {
+        final_merkle_root: rows.iter().rev().find_map(|r| r.merkle_root.clone()),
+        sources,
+        ..first.header.clone()
+    };
+    validate_sources(&header)?;
+    Ok(TraceDoc { header, rows })
+}
+
+/// Which trace of a `compare_traces` pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceSide {
+    A,
+    B,
+}
+
+/// A point where two traces of the same run disagree, at `(node_id, row_index)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceDivergence {
+    /// The row exists only in trace `present_in`.
+    MissingRow {
+        node_id: u32,
+        row_index: usize,
+        present_in: TraceSide,
+    },
+    /// First field (in `TraceRowSerde` order) that differs; values as compact JSON.
+    Field {
+        node_id: u32,
+        row_index: usize,
+        field: &'static str,
+        a: String,
+        b: String,
+    },
+}
+
+/// Every `TraceRowSerde` field except the `(node_id, row_index)` key, in declaration order.
+fn row_fields(r: &TraceRowSerde) -> [(&'static str, Value); 14] {
+    [
+        ("prev_hash", json!(r.prev_hash)),
+        ("author", json!(r.author)),
+        ("timestamp", json!(r.timestamp)),
+        ("payload_hash", json!(r.payload_hash)),
+        ("signature", json!(r.signature)),
+        ("parent_present", json!(r.parent_present)),
+        ("ancestor_check", json!(r.ancestor_check)),
+        (
+            "quarantined_until_before",
+            json!(r.quarantined_until_before),
+        ),
+        ("quarantined_until_after", json!(r.quarantined_until_after)),
+        ("merkle_root", json!(r.merkle_root)),
+        ("merkle_root_has", json!(r.merkle_root_has)),
+        ("preferred_tip", json!(r.preferred_tip)),
+        ("preferred_tip_has", json!(r.preferred_tip_has)),
+        ("author_weight_fp", json!(r.author_weight_fp)),
+    ]
+}
+
+fn rows_by_coordinate(doc: &TraceDoc) -> BTreeMap<(u32, usize), &TraceRowSerde> {
+    let mut rows = BTreeMap::new();
+    for r in &doc.rows {
+        rows.entry((r.node_id, r.row_index)).or_insert(r);
+    }
+    rows
+}
+
+/// Compare two traces of the same run row by row, aligned on `(node_id, row_index)` rather
+/// than position, so differently merged artifacts still line up. Reports, in coordinate
+/// order, rows missing from either side and the first differing field of each shared row.
+/// Empty means both implementations computed identical per-row values. Headers are not
+/// compared; if a trace repeats a coordinate, its first row is used.
+pub fn compare_traces(a: &TraceDoc, b: &TraceDoc) -> Vec<TraceDivergence> {
+    let (rows_a, mut rows_b) = (rows_by_coordinate(a), rows_by_coordinate(b));
+    let mut out = Vec::new();
+    for ((node_id, row_index), ra) in rows_a {
+        let Some(rb) = rows_b.remove(&(node_id, row_index)) else {
+            out.push(TraceDivergence::MissingRow {
+                node_id,
+                row_index,
+                present_in: TraceSide::A,
+            });
+            continue;
+        };
+        let first_diff = row_fields(ra)
+            .into_iter()
+            .zip(row_fields(rb))
+            .find(|((_, va), (_, vb))| va != vb);
+        if let Some(((field, va), (_, vb))) = first_diff {
+            out.push(TraceDivergence::Field {
+                node_id,
+                row_index,
+                field,
+                a: va.to_string(),
+                b: vb.to_string(),
+            });
+        }
+    }
+    out.extend(
+        rows_b
+            .into_keys()
+            .map(|(node_id, row_index)| TraceDivergence::MissingRow {
+                node_id,
+                row_index,
+                present_in: TraceSide::B,
+            }),
+    );
+    out.sort_by_key(|d| match d {
+        TraceDivergence::MissingRow {
+            node_id, row_index, ..
+        }
+        | TraceDivergence::Field {
+            node_id, row_index, ..
+        } => (*node_id, *row_index),
+    });
+    out
+}
+
 fn hex32(s: &str) -> Option<Hash> {
     if s.len() != 64 {
         return None;
@@ -86,9 +260,9 @@ fn hex64(s: &str) -> Option<Signature> {
     Some(out)
 }
 
-fn parse_row(row: &TraceRowSerde) -> Option<Event> {
+fn parse_row(version: u8, row: &TraceRowSerde) -> Option<Event> {
     Some(Event::from_raw(
-        crate::PROTOCOL_VERSION,
+        version,
         hex32(&row.prev_hash)?,
         hex32(&row.author)?,
         row.timestamp,
@@ -97,9 +271,162 @@ fn parse_row(row: &TraceRowSerde) -> Option<Event> {
     ))
 }
 
-/// Checks the merged trace deterministically. Returns Ok(()) if all invariants hold.
+/// Machine-readable class of a `TraceViolation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TraceViolationKind {
+    /// Header `sources` empty, unsorted or duplicated.
+    Sources,
+    ParseError,
+    ParentPresence,
+    /// Signature or timestamp rejected by `validate_event`.
+    Validation,
+    /// Event could not be linked into the ledger.
+    Link,
+    QuarantineWindow,
+    WeightBounds,
+    MerkleMismatch,
+    FinalRootMismatch,
+}
+
+impl TraceViolationKind {
+    /// Fatal violations stop the walk: every later row depends on the ledger state the
+    /// offending row failed to produce.
+    pub fn is_fatal(self) -> bool {
+        matches!(
+            self,
+            TraceViolationKind::ParseError
+                | TraceViolationKind::Validation
+                | TraceViolationKind::Link
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceViolation {
+    /// Offending row; None for header-level violations.
+    pub row: Option<usize>,
+    pub kind: TraceViolationKind,
+    pub message: String,
+}
+
+/// Every violation found in a trace, in the order `check_trace` would meet them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceReport {
+    pub violations: Vec<TraceViolation>,
+    /// True if a fatal violation ended the walk before the last row.
+    pub halted: bool,
+}
+
+impl TraceReport {
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    fn push(&mut self, row: Option<usize>, kind: TraceViolationKind, message: String) {
+        self.violations.push(TraceViolation { row, kind, message });
+    }
+}
+
+/// Checks the merged trace deterministically. Returns Ok(()) if all invariants hold,
+/// otherwise the message of the first violation `check_trace_report` finds.
 pub fn check_trace(doc: TraceDoc) -> Result<(), String> {
-    let cfg: SybilConfig = doc.header.sybil_config.into();
+    match check_trace_report(doc).violations.into_iter().next() {
+        Some(v) => Err(v.message),
+        None => Ok(()),
+    }
+}
+
+/// Checks every invariant of the merged trace and collects all violations instead of
+/// stopping at the first. Row-level invariant failures are recorded and the walk
+/// continues; parse, validation and link failures halt it (see `is_fatal`).
+pub fn check_trace_report(doc: TraceDoc) -> TraceReport {
+    report_rows(doc.header, doc.rows.into_iter().map(Ok))
+}
+
+/// Streaming form of `check_trace`: rows are checked as the iterator yields them, so a
+/// trace never has to be held in memory. A row that fails to deserialize is a parse error.
+pub fn check_trace_reader(
+    header: TraceHeader,
+    rows: impl Iterator<Item = Result<TraceRowSerde, serde_json::Error>>,
+) -> Result<(), String> {
+    let rows = rows
+        .enumerate()
+        .map(|(idx, r)| r.map_err(|e| format!("row {} parse error: {}", idx, e)));
+    match report_rows(header, rows).violations.into_iter().next() {
+        Some(v) => Err(v.message),
+        None => Ok(()),
+    }
+}
+
+/// Read an NDJSON trace: the first non-blank line is the `TraceHeader` object, each
+/// following non-blank line one `TraceRowSerde`. Only the header is parsed up front;
+/// rows are parsed lazily by the returned iterator, whose errors name the line.
+pub fn read_trace_ndjson<R: BufRead>(reader: R) -> Result<(TraceHeader, NdjsonRows<R>), String> {
+    let mut lines = NdjsonLines {
+        lines: reader.lines(),
+        line_no: 0,
+    };
+    let (line_no, text) = lines
+        .next_line()
+        .map_err(|e| format!("read error: {}", e))?
+        .ok_or("empty trace: missing header line")?;
+    let header = serde_json::from_str(&text)
+        .map_err(|e| format!("line {}: header parse error: {}", line_no, e))?;
+    Ok((header, NdjsonRows { lines }))
+}
+
+struct NdjsonLines<R> {
+    lines: std::io::Lines<R>,
+    line_no: usize,
+}
+
+impl<R: BufRead> NdjsonLines<R> {
+    /// Next non-blank line with its 1-based number.
+    fn next_line(&mut self) -> std::io::Result<Option<(usize, String)>> {
+        for line in self.lines.by_ref() {
+            self.line_no += 1;
+            let line = line?;
+            if !line.trim().is_empty() {
+                return Ok(Some((self.line_no, line)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Row iterator returned by `read_trace_ndjson`.
+pub struct NdjsonRows<R> {
+    lines: NdjsonLines<R>,
+}
+
+impl<R: BufRead> Iterator for NdjsonRows<R> {
+    type Item = Result<TraceRowSerde, serde_json::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line_error = |line_no: usize, e: &dyn std::fmt::Display| {
+            <serde_json::Error as serde::de::Error>::custom(format!("line {}: {}", line_no, e))
+        };
+        match self.lines.next_line() {
+            Ok(Some((line_no, text))) => {
+                Some(serde_json::from_str(&text).map_err(|e| line_error(line_no, &e)))
+            }
+            Ok(None) => None,
+            Err(e) => Some(Err(line_error(self.lines.line_no, &e))),
+        }
+    }
+}
+
+fn report_rows(
+    header: TraceHeader,
+    rows: impl Iterator<Item = Result<TraceRowSerde, String>>,
+) -> TraceReport {
+    use TraceViolationKind as K;
+
+    let mut report = TraceReport::default();
+    if let Err(e) = validate_sources(&header) {
+        report.push(None, K::Sources, e);
+    }
+    let cfg: SybilConfig = header.sybil_config.into();
     let mut overlay = SybilOverlay::new(cfg.clone());
     let mut ledger = Ledger::new();
     let mut vctxs: std::collections::HashMap<PublicKey, ValidationContext> =
@@ -107,33 +434,59 @@ pub fn check_trace(doc: TraceDoc) -> Result<(), String> {
 
     let mut last_root: Option<Hash> = None;
 
-    for (idx, r) in doc.rows.iter().enumerate() {
-        let ev = parse_row(r).ok_or_else(|| format!("row {} parse error", idx))?;
+    for (idx, r) in rows.enumerate() {
+        let row = Some(idx);
+        let r = match r {
+            Ok(r) => r,
+            Err(e) => {
+                report.push(row, K::ParseError, e);
+                report.halted = true;
+                return report;
+            }
+        };
+        let Some(ev) = parse_row(header.protocol_version, &r) else {
+            report.push(row, K::ParseError, format!("row {} parse error", idx));
+            report.halted = true;
+            return report;
+        };
 
         // parent_present check
         let parent_is_zero = ev.prev_hash == ZERO_HASH;
         let parent_known = ledger.get_event(&ev.prev_hash).is_some();
         if !parent_is_zero && !parent_known && r.parent_present {
-            return Err(format!(
-                "row {} parent_present=true but parent unknown",
-                idx
-            ));
+            report.push(
+                row,
+                K::ParentPresence,
+                format!("row {} parent_present=true but parent unknown", idx),
+            );
         }
         if !parent_is_zero && parent_known && !r.parent_present {
-            return Err(format!("row {} parent_present=false but parent known", idx));
+            report.push(
+                row,
+                K::ParentPresence,
+                format!("row {} parent_present=false but parent known", idx),
+            );
         }
 
         // Validate signature/timestamp
-        let ctx = vctxs
-            .entry(ev.author)
-            .or_insert_with(ValidationContext::new);
-        validate_event(&ev, ctx).map_err(|e| format!("row {} validate error {:?}", idx, e))?;
+        let ctx = vctxs.entry(ev.author).or_default();
+        if let Err(e) = validate_event(&ev, ctx) {
+            report.push(
+                row,
+                K::Validation,
+                format!("row {} validate error {:?}", idx, e),
+            );
+            report.halted = true;
+            return report;
+        }
 
         // Link
         let h = Ledger::hash_event(&ev);
-        ledger
-            .try_add_event(ev.clone())
-            .map_err(|e| format!("row {} link error {:?}", idx, e))?;
+        if let Err(e) = ledger.try_add_event(ev.clone()) {
+            report.push(row, K::Link, format!("row {} link error {:?}", idx, e));
+            report.halted = true;
+            return report;
+        }
 
         // Overlay observe
         overlay.observe_event(&ledger, r.timestamp, r.node_id, &ev, h);
@@ -142,40 +495,347 @@ pub fn check_trace(doc: TraceDoc) -> Result<(), String> {
         if !r.ancestor_check
             && r.quarantined_until_after < r.quarantined_until_before + cfg.quarantine_ticks
         {
-            return Err(format!(
-                "row {} quarantine_after too small: before {} after {}",
-                idx, r.quarantined_until_before, r.quarantined_until_after
-            ));
+            report.push(
+                row,
+                K::QuarantineWindow,
+                format!(
+                    "row {} quarantine_after too small: before {} after {}",
+                    idx, r.quarantined_until_before, r.quarantined_until_after
+                ),
+            );
         }
 
         // Weight bounds
         if r.author_weight_fp > cfg.fixed_point_scale {
-            return Err(format!("row {} author_weight_fp out of bounds", idx));
+            report.push(
+                row,
+                K::WeightBounds,
+                format!("row {} author_weight_fp out of bounds", idx),
+            );
         }
         if r.timestamp < r.quarantined_until_after && r.author_weight_fp != 0 {
-            return Err(format!("row {} weight not zero during quarantine", idx));
+            report.push(
+                row,
+                K::WeightBounds,
+                format!("row {} weight not zero during quarantine", idx),
+            );
+        }
+        // Slash: once the checker sees an author equivocate, its weight is zero for good.
+        if cfg.policy == EquivocationPolicy::Slash
+            && overlay.is_slashed(&ev.author)
+            && r.author_weight_fp != 0
+        {
+            report.push(
+                row,
+                K::WeightBounds,
+                format!("row {} weight not zero for slashed author", idx),
+            );
         }
 
         // Merkle root
         if let Some(root) = ledger.merkle_root() {
             last_root = Some(root);
-            if let Some(mr_str) = &r.merkle_root {
-                if let Some(mr_row) = hex32(mr_str) {
-                    if mr_row != root {
-                        return Err(format!("row {} merkle_root mismatch", idx));
-                    }
-                }
+            if let Some(mr_row) = r.merkle_root.as_deref().and_then(hex32) && mr_row != root {
+                report.push(
+                    row,
+                    K::MerkleMismatch,
+                    format!("row {} merkle_root mismatch", idx),
+                );
             }
         }
     }
 
-    if let (Some(hdr_root), Some(last)) = (&doc.header.final_merkle_root, last_root) {
-        if let Some(expected) = hex32(hdr_root) {
-            if expected != last {
-                return Err("final_merkle_root mismatch".into());
-            }
+    // A header root with no rows to back it (e.g. an empty stream) is a mismatch too.
+    if let Some(expected) = header.final_merkle_root.as_deref().and_then(hex32)
+        && last_root != Some(expected)
+    {
+        report.push(
+            None,
+            K::FinalRootMismatch,
+            "final_merkle_root mismatch".into(),
+        );
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node_trace(node_id: u32, source: &str, timestamps: &[u64]) -> TraceDoc {
+        let rows: Vec<String> = timestamps
+            .iter()
+            .enumerate()
+            .map(|(i, ts)| {
+                format!(
+                    r#"{{"node_id":{node_id},"row_index":{i},"prev_hash":"","author":"","timestamp":{ts},
+                    "payload_hash":"","signature":"","parent_present":false,"ancestor_check":true,
+                    "quarantined_until_before":0,"quarantined_until_after":0,
+                    "merkle_root":"{node_id}-{i}","merkle_root_has":true,"preferred_tip":null,
+                    "preferred_tip_has":false,"author_weight_fp":0}}"#
+                )
+            })
+            .collect();
+        let json = format!(
+            r#"{{"header":{{"protocol_version":1,"max_backward_skew":100,"max_pending_total":16,
+            "max_drain_steps":16,"sybil_config":{{"warmup_ticks":4,"quarantine_ticks":12,
+            "fixed_point_scale":1000,"max_link_walk":64}},"final_merkle_root":null,
+            "sources":["{source}"]}},"rows":[{}]}}"#,
+            rows.join(",")
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn merge_orders_rows_and_populates_sources() {
+        let n1 = node_trace(1, "trace_node1.json", &[1, 3, 5]);
+        let n0 = node_trace(0, "trace_node0.json", &[2, 3, 4]);
+        let merged = merge_traces(&[n1, n0]).unwrap();
+
+        let order: Vec<(u64, u32, usize)> = merged
+            .rows
+            .iter()
+            .map(|r| (r.timestamp, r.node_id, r.row_index))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                (1, 1, 0),
+                (2, 0, 0),
+                (3, 0, 1),
+                (3, 1, 1),
+                (4, 0, 2),
+                (5, 1, 2)
+            ]
+        );
+        assert_eq!(
+            merged.header.sources,
+            vec!["trace_node0.json", "trace_node1.json"]
+        );
+        assert_eq!(merged.header.final_merkle_root.as_deref(), Some("1-2"));
+    }
+
+    #[test]
+    fn compare_traces_reports_first_differing_field() {
+        let a = merge_traces(&[
+            node_trace(0, "trace_node0.json", &[2, 3, 4]),
+            node_trace(1, "trace_node1.json", &[1, 3]),
+        ])
+        .unwrap();
+        assert!(compare_traces(&a, &a).is_empty());
+
+        // Row order does not matter; only (node_id, row_index) does.
+        let mut b = a.clone();
+        b.rows.reverse();
+        let pos = b
+            .rows
+            .iter()
+            .position(|r| (r.node_id, r.row_index) == (1, 1))
+            .unwrap();
+        b.rows[pos].author_weight_fp = 7;
+        assert_eq!(
+            compare_traces(&a, &b),
+            vec![TraceDivergence::Field {
+                node_id: 1,
+                row_index: 1,
+                field: "author_weight_fp",
+                a: "0".into(),
+                b: "7".into(),
+            }]
+        );
+
+        // Earlier fields win; absent rows are flagged on the side that has them.
+        b.rows[pos].merkle_root = None;
+        b.rows.retain(|r| (r.node_id, r.row_index) != (0, 2));
+        b.rows
+            .push(node_trace(2, "trace_node2.json", &[9]).rows.remove(0));
+        assert_eq!(
+            compare_traces(&a, &b),
+            vec![
+                TraceDivergence::MissingRow {
+                    node_id: 0,
+                    row_index: 2,
+                    present_in: TraceSide::A,
+                },
+                TraceDivergence::Field {
+                    node_id: 1,
+                    row_index: 1,
+                    field: "merkle_root",
+                    a: "\"1-1\"".into(),
+                    b: "null".into(),
+                },
+                TraceDivergence::MissingRow {
+                    node_id: 2,
+                    row_index: 0,
+                    present_in: TraceSide::B,
+                },
+            ]
+        );
+    }
+
+    fn signed(secret: &[u8; 32], prev: Hash, ts: u64, tag: u8) -> (Event, TraceRowSerde) {
+        let sk = ed25519_dalek::SecretKey::from_bytes(secret).unwrap();
+        let author = ed25519_dalek::PublicKey::from(&sk).to_bytes();
+        let mut ev = Event::new(prev, author, ts, [tag; 32], [0u8; 64]);
+        ev.signature = crate::validation::compute_signature_with_secret(&ev, secret);
+        let row = TraceRowSerde {
+            node_id: 0,
+            row_index: 0,
+            prev_hash: hex::encode(ev.prev_hash),
+            author: hex::encode(ev.author),
+            timestamp: ts,
+            payload_hash: hex::encode(ev.payload_hash),
+            signature: hex::encode(ev.signature),
+            parent_present: prev != ZERO_HASH,
+            ancestor_check: true,
+            quarantined_until_before: 0,
+            quarantined_until_after: 0,
+            merkle_root: None,
+            merkle_root_has: false,
+            preferred_tip: None,
+            preferred_tip_has: false,
+            author_weight_fp: 0,
+        };
+        (ev, row)
+    }
+
+    /// Author equivocates (two genesis events at height 0), then keeps building after the
+    /// quarantine window with the given weight on the late row.
+    fn equivocation_trace(policy: PolicySerde, late_weight: u64) -> TraceDoc {
+        let secret = [0x21; 32];
+        let (_, r1) = signed(&secret, ZERO_HASH, 1, 1);
+        let (e2, mut r2) = signed(&secret, ZERO_HASH, 2, 2);
+        r2.ancestor_check = false;
+        r2.quarantined_until_after = 2 + 12;
+        let (_, mut r3) = signed(&secret, Ledger::hash_event(&e2), 20, 3);
+        r3.quarantined_until_before = 14;
+        r3.quarantined_until_after = 14;
+        r3.author_weight_fp = late_weight;
+        let mut doc = node_trace(0, "trace_node0.json", &[]);
+        doc.header.protocol_version = crate::PROTOCOL_VERSION;
+        doc.header.sybil_config.quarantine_ticks = 12;
+        doc.header.sybil_config.policy = policy;
+        doc.rows = vec![r1, r2, r3];
+        doc
+    }
+
+    #[test]
+    fn slash_policy_requires_permanent_zero_weight() {
+        assert_eq!(
+            check_trace(equivocation_trace(PolicySerde::Quarantine, 500)),
+            Ok(())
+        );
+        assert_eq!(
+            check_trace(equivocation_trace(PolicySerde::Slash, 0)),
+            Ok(())
+        );
+        let err = check_trace(equivocation_trace(PolicySerde::Slash, 500)).unwrap_err();
+        assert!(err.contains("slashed"), "{}", err);
+
+        let json = r#"{"warmup_ticks":1,"quarantine_ticks":2,"fixed_point_scale":3,
+            "max_link_walk":4,"policy":"slash"}"#;
+        let cfg: SybilConfigSerde = serde_json::from_str(json).unwrap();
+        assert_eq!(cfg.policy, PolicySerde::Slash);
+    }
+
+    #[test]
+    fn unsorted_or_duplicate_sources_rejected() {
+        let mut doc = node_trace(0, "b.json", &[1]);
+        doc.header.sources = vec!["b.json".into(), "a.json".into()];
+        assert!(validate_sources(&doc.header).is_err());
+        doc.header.sources = vec!["a.json".into(), "a.json".into()];
+        assert!(validate_sources(&doc.header).is_err());
+        doc.header.sources.clear();
+        assert!(check_trace(doc).is_err());
+
+        let mut other = node_trace(1, "c.json", &[1]);
+        other.header.max_backward_skew = 7;
+        assert!(merge_traces(&[node_trace(0, "b.json", &[1]), other]).is_err());
+    }
+
+    #[test]
+    fn report_collects_every_non_fatal_violation() {
+        let mut doc = equivocation_trace(PolicySerde::Slash, 0);
+        doc.header.sources.clear();
+        doc.rows[0].author_weight_fp = 5_000;
+        doc.rows[1].quarantined_until_after = 3;
+        doc.rows[2].author_weight_fp = 500;
+        doc.rows[2].merkle_root = Some(hex::encode([0xAB; 32]));
+
+        let report = check_trace_report(doc.clone());
+        let found: Vec<(Option<usize>, TraceViolationKind)> =
+            report.violations.iter().map(|v| (v.row, v.kind)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (None, TraceViolationKind::Sources),
+                (Some(0), TraceViolationKind::WeightBounds),
+                (Some(1), TraceViolationKind::QuarantineWindow),
+                (Some(2), TraceViolationKind::WeightBounds),
+                (Some(2), TraceViolationKind::MerkleMismatch),
+            ]
+        );
+        assert!(!report.halted);
+        assert_eq!(check_trace(doc), Err(report.violations[0].message.clone()));
+    }
+
+    #[test]
+    fn report_halts_on_link_failure() {
+        let mut doc = equivocation_trace(PolicySerde::Quarantine, 0);
+        doc.rows[0].author_weight_fp = 5_000;
+        // Replaying row 0 is a valid event that cannot be linked twice.
+        doc.rows[1] = doc.rows[0].clone();
+        let report = check_trace_report(doc);
+        assert!(report.halted);
+        let last = report.violations.last().unwrap();
+        assert_eq!((last.row, last.kind), (Some(1), TraceViolationKind::Link));
+        assert_eq!(report.violations[0].kind, TraceViolationKind::WeightBounds);
+    }
+
+    fn to_ndjson(doc: &TraceDoc) -> String {
+        let mut out = serde_json::to_string(&doc.header).unwrap();
+        for r in &doc.rows {
+            out.push('\n');
+            out.push_str(&serde_json::to_string(r).unwrap());
         }
+        out.push('\n');
+        out
     }
 
-    Ok(())
+    fn check_ndjson(text: &str) -> Result<(), String> {
+        let (header, rows) = read_trace_ndjson(text.as_bytes())?;
+        check_trace_reader(header, rows)
+    }
+
+    #[test]
+    fn ndjson_stream_matches_in_memory_check() {
+        for (policy, weight) in [(PolicySerde::Slash, 0), (PolicySerde::Slash, 500)] {
+            let doc = equivocation_trace(policy, weight);
+            assert_eq!(check_ndjson(&to_ndjson(&doc)), check_trace(doc));
+        }
+    }
+
+    #[test]
+    fn ndjson_empty_stream_checks_final_root() {
+        let mut doc = equivocation_trace(PolicySerde::Quarantine, 0);
+        doc.rows.clear();
+        assert_eq!(check_ndjson(&to_ndjson(&doc)), Ok(()));
+        doc.header.final_merkle_root = Some(hex::encode([7u8; 32]));
+        assert_eq!(
+            check_ndjson(&to_ndjson(&doc)),
+            Err("final_merkle_root mismatch".into())
+        );
+        assert!(check_ndjson("\n\n").is_err());
+    }
+
+    #[test]
+    fn ndjson_malformed_row_reports_line_number() {
+        let doc = equivocation_trace(PolicySerde::Quarantine, 0);
+        let mut lines: Vec<String> = to_ndjson(&doc).lines().map(String::from).collect();
+        lines.insert(2, String::new());
+        lines[3] = "{\"node_id\":".into();
+        let err = check_ndjson(&lines.join("\n")).unwrap_err();
+        assert!(err.starts_with("row 1 parse error: line 4:"), "{}", err);
+    }
 }
@@ -1,7 +1,11 @@
 // Validation: deterministic, in-memory; enforces version, ed25519 signature, and bounded timestamp skew.
 // Crypto here is reference-grade only (not hardened).
-use crate::event::{Event, Signature};
-use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signature as DalekSignature, Signer, Verifier};
+use crate::event::{Event, PayloadInfo, PublicKey as AuthorKey, Signature};
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signature as DalekSignature, Signer};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 #[derive(Debug, PartialEq)]
@@ -9,11 +13,22 @@ pub enum ValidationError {
     InvalidVersion,
     InvalidSignature,
     TimestampNonMonotonic,
+    /// Timestamp exceeds the caller's `now` by more than `max_forward_skew`.
+    TimestampTooFarAhead,
+    /// Payload longer than `max_payload_len`.
+    PayloadTooLarge,
+    /// SHA256 of the payload differs from the event's `payload_hash`.
+    PayloadHashMismatch,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ValidationContext {
     pub last_timestamp: u64,
+    /// Allowed lead of an event timestamp over `now` in `validate_event_at`.
+    /// Default u64::MAX (no forward bound).
+    pub max_forward_skew: u64,
+    /// Largest payload accepted by the payload checks. Default u64::MAX (no limit).
+    pub max_payload_len: u64,
 }
 
 // Runtime-configurable backward skew bound (default 4).
@@ -29,13 +44,17 @@ pub fn compute_signature_with_secret(event: &Event, secret: &[u8; 32]) -> Signat
     let secret = SecretKey::from_bytes(secret).expect("secret key must be 32 bytes");
     let public: PublicKey = (&secret).into();
     let kp = Keypair { secret, public };
-    let sig = kp.sign(&event.canonical_bytes());
+    let sig = kp.sign(&event.signing_input());
     sig.to_bytes()
 }
 
 impl ValidationContext {
     pub fn new() -> Self {
-        ValidationContext { last_timestamp: 0 }
+        ValidationContext {
+            last_timestamp: 0,
+            max_forward_skew: u64::MAX,
+            max_payload_len: u64::MAX,
+        }
     }
 }
 
@@ -45,35 +64,251 @@ impl Default for ValidationContext {
     }
 }
 
-fn to_verifying_key(pk: &[u8; 32]) -> Option<PublicKey> {
-    PublicKey::from_bytes(pk).ok()
+/// Serialized `ValidationContext` size: `last_timestamp`, `max_forward_skew`,
+/// `max_payload_len`, each u64 LE.
+pub const CONTEXT_LEN: usize = 24;
+
+impl ValidationContext {
+    pub fn to_bytes(&self) -> [u8; CONTEXT_LEN] {
+        let mut out = [0u8; CONTEXT_LEN];
+        out[0..8].copy_from_slice(&self.last_timestamp.to_le_bytes());
+        out[8..16].copy_from_slice(&self.max_forward_skew.to_le_bytes());
+        out[16..24].copy_from_slice(&self.max_payload_len.to_le_bytes());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8; CONTEXT_LEN]) -> Self {
+        let field = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        ValidationContext {
+            last_timestamp: field(0),
+            max_forward_skew: field(8),
+            max_payload_len: field(16),
+        }
+    }
+}
+
+/// Context store file header; followed by a u64 LE author count and, per author in
+/// ascending key order, the 32-byte key and `ValidationContext::to_bytes`.
+const CONTEXT_STORE_MAGIC: &[u8; 8] = b"dvel-vc1";
+
+/// Per-author `ValidationContext`s that survive restarts, so an author's timestamp
+/// high-water mark cannot be reset by restarting the node.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationContextStore {
+    contexts: BTreeMap<AuthorKey, ValidationContext>,
+}
+
+impl ValidationContextStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, author: &AuthorKey) -> Option<&ValidationContext> {
+        self.contexts.get(author)
+    }
+
+    /// The author's context, created with `ValidationContext::new()` on first use.
+    pub fn context_mut(&mut self, author: AuthorKey) -> &mut ValidationContext {
+        self.contexts.entry(author).or_default()
+    }
+
+    /// `validate_event` against the event author's context.
+    pub fn validate(&mut self, event: &Event) -> Result<(), ValidationError> {
+        validate_event(event, self.context_mut(event.author))
+    }
+
+    pub fn len(&self) -> usize {
+        self.contexts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contexts.is_empty()
+    }
+
+    /// Write every context, sorted by author, so equal stores give identical files.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(CONTEXT_STORE_MAGIC)?;
+        out.write_all(&(self.contexts.len() as u64).to_le_bytes())?;
+        for (author, ctx) in &self.contexts {
+            out.write_all(author)?;
+            out.write_all(&ctx.to_bytes())?;
+        }
+        out.flush()
+    }
+
+    /// Read a store written by `save`. Malformed files are `InvalidData`.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+
+        let rest = bytes
+            .strip_prefix(CONTEXT_STORE_MAGIC.as_slice())
+            .ok_or_else(|| invalid("not a validation context store"))?;
+        let (count, rest) = rest
+            .split_first_chunk::<8>()
+            .ok_or_else(|| invalid("truncated validation context store"))?;
+        let count = u64::from_le_bytes(*count);
+        const RECORD_LEN: usize = 32 + CONTEXT_LEN;
+        if rest.len() % RECORD_LEN != 0 || (rest.len() / RECORD_LEN) as u64 != count {
+            return Err(invalid(
+                "validation context store length does not match count",
+            ));
+        }
+
+        let mut contexts = BTreeMap::new();
+        for rec in rest.chunks_exact(RECORD_LEN) {
+            let (author, ctx) = rec.split_at(32);
+            let author: AuthorKey = author.try_into().unwrap();
+            let ctx = ValidationContext::from_bytes(ctx.try_into().unwrap());
+            if contexts.insert(author, ctx).is_some() {
+                return Err(invalid("duplicate author in validation context store"));
+            }
+        }
+        Ok(ValidationContextStore { contexts })
+    }
 }
 
-fn to_signature(sig: &[u8; 64]) -> Option<DalekSignature> {
-    DalekSignature::from_bytes(sig).ok()
+/// Signature-only check over many events (no version/timestamp/context logic), meant to run
+/// before per-author sequential validation. Uses ed25519 batch verification; if the batch
+/// fails, events are re-checked one by one so every failing index is reported (ascending).
+pub fn verify_batch(events: &[Event]) -> Result<(), Vec<usize>> {
+    let mut failed = Vec::new();
+    let mut idxs = Vec::with_capacity(events.len());
+    let mut messages = Vec::with_capacity(events.len());
+    let mut sigs = Vec::with_capacity(events.len());
+    let mut keys = Vec::with_capacity(events.len());
+    for (i, ev) in events.iter().enumerate() {
+        match (
+            PublicKey::from_bytes(&ev.author),
+            DalekSignature::from_bytes(&ev.signature),
+        ) {
+            (Ok(pk), Ok(sig)) => {
+                idxs.push(i);
+                messages.push(ev.signing_input());
+                sigs.push(sig);
+                keys.push(pk);
+            }
+            _ => failed.push(i),
+        }
+    }
+
+    let msg_refs: Vec<&[u8]> = messages.iter().map(Vec::as_slice).collect();
+    if ed25519_dalek::verify_batch(&msg_refs, &sigs, &keys).is_err() {
+        failed.extend(idxs.into_iter().filter(|&i| !events[i].verify_signature()));
+        failed.sort_unstable();
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(failed)
+    }
 }
 
 /// Stateful validator:
 /// - updates ctx.last_timestamp
 /// - caller must present events in ledger order
 pub fn validate_event(event: &Event, ctx: &mut ValidationContext) -> Result<(), ValidationError> {
+    validate_event_inner(event, ctx, None)
+}
+
+/// Like `validate_event`, additionally rejecting events more than `ctx.max_forward_skew`
+/// ahead of the caller-supplied `now` (delta == skew passes).
+pub fn validate_event_at(
+    event: &Event,
+    ctx: &mut ValidationContext,
+    now: u64,
+) -> Result<(), ValidationError> {
+    validate_event_inner(event, ctx, Some(now))
+}
+
+/// Structural-only validation that SKIPS ED25519 SIGNATURE VERIFICATION: checks version,
+/// timestamp policy (as `validate_event`) and that `author` decodes as a public key, then
+/// advances `ctx`. Only for sources the caller already trusts, e.g. replaying a ledger this
+/// node persisted itself; anything received from peers must go through `validate_event`.
+pub fn validate_event_structural(
+    event: &Event,
+    ctx: &mut ValidationContext,
+) -> Result<(), ValidationError> {
+    check_version(event)?;
+    // Well-formed author key; a key that cannot decode could never carry a valid signature.
+    if PublicKey::from_bytes(&event.author).is_err() {
+        return Err(ValidationError::InvalidSignature);
+    }
+    check_timestamp(event, ctx, None)
+}
+
+/// Payload check for events whose payload is tracked separately: `info.len` must not exceed
+/// `max_len` and `info.hash` must equal the event's `payload_hash`. Stateless.
+pub fn validate_payload(
+    event: &Event,
+    info: &PayloadInfo,
+    max_len: u64,
+) -> Result<(), ValidationError> {
+    if info.len > max_len {
+        return Err(ValidationError::PayloadTooLarge);
+    }
+    if info.hash != event.payload_hash {
+        return Err(ValidationError::PayloadHashMismatch);
+    }
+    Ok(())
+}
+
+/// `validate_event` plus the payload bytes: length is checked against `ctx.max_payload_len`
+/// before hashing, then the hash against `payload_hash`. The context only advances if every
+/// check passes.
+pub fn validate_event_with_payload(
+    event: &Event,
+    ctx: &mut ValidationContext,
+    payload: &[u8],
+) -> Result<(), ValidationError> {
+    if payload.len() as u64 > ctx.max_payload_len {
+        return Err(ValidationError::PayloadTooLarge);
+    }
+    validate_payload(event, &PayloadInfo::of(payload), ctx.max_payload_len)?;
+    validate_event_inner(event, ctx, None)
+}
+
+fn validate_event_inner(
+    event: &Event,
+    ctx: &mut ValidationContext,
+    now: Option<u64>,
+) -> Result<(), ValidationError> {
+    check_version(event)?;
+
+    // --- Signature check (ed25519 over the version's signing input) ---
+    if !event.verify_signature() {
+        return Err(ValidationError::InvalidSignature);
+    }
+
+    check_timestamp(event, ctx, now)
+}
+
+fn check_version(event: &Event) -> Result<(), ValidationError> {
     // --- Version check (hard rule) ---
-    if event.version != crate::PROTOCOL_VERSION {
+    // Version 1 (unprefixed signing input) is still accepted for existing traces.
+    if event.version == 0 || event.version > crate::PROTOCOL_VERSION {
         return Err(ValidationError::InvalidVersion);
     }
+    Ok(())
+}
 
-    // --- Signature check (ed25519) ---
-    let vk = to_verifying_key(&event.author).ok_or(ValidationError::InvalidSignature)?;
-    let sig = to_signature(&event.signature).ok_or(ValidationError::InvalidSignature)?;
-    vk.verify(&event.canonical_bytes(), &sig)
-        .map_err(|_| ValidationError::InvalidSignature)?;
-
+fn check_timestamp(
+    event: &Event,
+    ctx: &mut ValidationContext,
+    now: Option<u64>,
+) -> Result<(), ValidationError> {
     // --- Timestamp monotonicity with bounded skew ---
     // Permit small backward steps; reject large rewinds.
     let skew = MAX_BACKWARD_SKEW.load(Ordering::Relaxed);
     if event.timestamp.saturating_add(skew) < ctx.last_timestamp {
         return Err(ValidationError::TimestampNonMonotonic);
     }
+    // Bounded lead over the injected clock (opt-in via validate_event_at).
+    if let Some(now) = now && event.timestamp.saturating_sub(now) > ctx.max_forward_skew {
+        return Err(ValidationError::TimestampTooFarAhead);
+    }
     // Keep monotone max for forward progress
     if event.timestamp > ctx.last_timestamp {
         ctx.last_timestamp = event.timestamp;
@@ -81,3 +316,168 @@ pub fn validate_event(event: &Event, ctx: &mut ValidationContext) -> Result<(),
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::ZERO_HASH;
+    use ed25519_dalek::{PublicKey, SecretKey};
+
+    fn signed_at(ts: u64) -> Event {
+        let secret = [3u8; 32];
+        let author = PublicKey::from(&SecretKey::from_bytes(&secret).unwrap()).to_bytes();
+        let mut ev = Event::new(ZERO_HASH, author, ts, [0x33; 32], [0u8; 64]);
+        ev.signature = compute_signature_with_secret(&ev, &secret);
+        ev
+    }
+
+    #[test]
+    fn forward_skew_boundary() {
+        let now = 1_000;
+        let mut ctx = ValidationContext {
+            max_forward_skew: 30,
+            ..ValidationContext::new()
+        };
+        assert_eq!(
+            validate_event_at(&signed_at(now + 30), &mut ctx, now),
+            Ok(())
+        );
+        assert_eq!(
+            validate_event_at(&signed_at(now + 31), &mut ctx, now),
+            Err(ValidationError::TimestampTooFarAhead)
+        );
+        assert_eq!(
+            ctx.last_timestamp,
+            now + 30,
+            "rejected event leaves ctx unchanged"
+        );
+        assert_eq!(
+            validate_event_at(&signed_at(now + 28), &mut ctx, now + 100),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn verify_batch_reports_corrupt_index() {
+        let events: Vec<Event> = (0..100).map(|i| signed_at(10 + i)).collect();
+        assert_eq!(verify_batch(&events), Ok(()));
+        assert_eq!(verify_batch(&[]), Ok(()));
+
+        let mut bad = events.clone();
+        bad[37].signature[5] ^= 0x01;
+        assert_eq!(verify_batch(&bad), Err(vec![37]));
+
+        bad[90].payload_hash[0] ^= 0x01;
+        assert_eq!(verify_batch(&bad), Err(vec![37, 90]));
+    }
+
+    #[test]
+    fn forward_skew_defaults_to_unbounded() {
+        let mut ctx = ValidationContext::new();
+        assert_eq!(validate_event_at(&signed_at(u64::MAX), &mut ctx, 0), Ok(()));
+        let mut ctx = ValidationContext {
+            max_forward_skew: 0,
+            ..ValidationContext::new()
+        };
+        // Plain validate_event never consults the forward bound.
+        assert_eq!(validate_event(&signed_at(u64::MAX), &mut ctx), Ok(()));
+    }
+
+    #[test]
+    fn structural_validation_skips_signature() {
+        let mut ev = signed_at(50);
+        ev.signature[0] ^= 0x01;
+
+        let mut ctx = ValidationContext::new();
+        assert_eq!(validate_event_structural(&ev, &mut ctx), Ok(()));
+        assert_eq!(ctx.last_timestamp, 50);
+
+        let mut ctx = ValidationContext::new();
+        assert_eq!(
+            validate_event(&ev, &mut ctx),
+            Err(ValidationError::InvalidSignature)
+        );
+
+        // Version and timestamp rules still apply.
+        let mut stale = signed_at(10);
+        stale.signature = [0u8; 64];
+        let mut ctx = ValidationContext::new();
+        ctx.last_timestamp = 50;
+        assert_eq!(
+            validate_event_structural(&stale, &mut ctx),
+            Err(ValidationError::TimestampNonMonotonic)
+        );
+        stale.version = 0;
+        assert_eq!(
+            validate_event_structural(&stale, &mut ctx),
+            Err(ValidationError::InvalidVersion)
+        );
+    }
+
+    #[test]
+    fn context_store_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("contexts.bin");
+
+        let mut store = ValidationContextStore::new();
+        assert_eq!(store.validate(&signed_at(100)), Ok(()));
+        store.context_mut([9u8; 32]).max_payload_len = 4096;
+        store.save(&path).unwrap();
+
+        let mut reloaded = ValidationContextStore::load(&path).unwrap();
+        assert_eq!(reloaded, store);
+        assert_eq!(reloaded.len(), 2);
+        // A replay far behind the persisted high-water mark is still rejected.
+        assert_eq!(
+            reloaded.validate(&signed_at(50)),
+            Err(ValidationError::TimestampNonMonotonic)
+        );
+        // A fresh store would have accepted it.
+        assert_eq!(
+            ValidationContextStore::new().validate(&signed_at(50)),
+            Ok(())
+        );
+
+        let ctx = reloaded.get(&[9u8; 32]).unwrap();
+        assert_eq!(ValidationContext::from_bytes(&ctx.to_bytes()), *ctx);
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.pop();
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(ValidationContextStore::load(&path).is_err());
+    }
+
+    #[test]
+    fn payload_length_and_hash_enforced() {
+        let payload = b"payload bytes".to_vec();
+        let secret = [3u8; 32];
+        let author = PublicKey::from(&SecretKey::from_bytes(&secret).unwrap()).to_bytes();
+        let info = PayloadInfo::of(&payload);
+        let mut ev = Event::new(ZERO_HASH, author, 5, info.hash, [0u8; 64]);
+        ev.signature = compute_signature_with_secret(&ev, &secret);
+
+        let mut ctx = ValidationContext {
+            max_payload_len: payload.len() as u64 - 1,
+            ..ValidationContext::new()
+        };
+        assert_eq!(
+            validate_event_with_payload(&ev, &mut ctx, &payload),
+            Err(ValidationError::PayloadTooLarge)
+        );
+        assert_eq!(ctx.last_timestamp, 0);
+
+        ctx.max_payload_len = payload.len() as u64;
+        assert_eq!(
+            validate_event_with_payload(&ev, &mut ctx, b"other bytes"),
+            Err(ValidationError::PayloadHashMismatch)
+        );
+        assert_eq!(validate_event_with_payload(&ev, &mut ctx, &payload), Ok(()));
+        assert_eq!(ctx.last_timestamp, 5);
+
+        assert_eq!(validate_payload(&ev, &info, info.len), Ok(()));
+        assert_eq!(
+            validate_payload(&ev, &info, info.len - 1),
+            Err(ValidationError::PayloadTooLarge)
+        );
+    }
+}
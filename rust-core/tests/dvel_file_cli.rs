@@ -0,0 +1,283 @@
+use std::process::Command;
+
+fn dvel_file() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_dvel-file"))
+}
+
+#[test]
+fn selfcheck_succeeds() {
+    let out = dvel_file()
+        .arg("selfcheck")
+        .output()
+        .expect("run dvel-file");
+    assert!(
+        out.status.success(),
+        "selfcheck failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "OK");
+}
+
+#[test]
+fn selfcheck_rejects_extra_args() {
+    let out = dvel_file()
+        .args(["selfcheck", "--bogus"])
+        .output()
+        .expect("run dvel-file");
+    assert!(!out.status.success());
+}
+
+#[test]
+fn upload_with_jobs_round_trips() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("big.bin");
+    let data: Vec<u8> = (0..50_000u32).map(|i| (i % 253) as u8).collect();
+    std::fs::write(&input, &data).unwrap();
+    let out_dir = dir.path().join("chunks");
+
+    let out = dvel_file()
+        .arg("upload")
+        .arg(&input)
+        .arg(&out_dir)
+        .args([
+            "1000",
+            "--jobs",
+            "2",
+            "--readahead",
+            "4",
+            "--io-buffer",
+            "4096",
+        ])
+        .output()
+        .expect("run dvel-file");
+    assert!(
+        out.status.success(),
+        "upload failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let rebuilt = dir.path().join("rebuilt.bin");
+    let out = dvel_file()
+        .arg("download")
+        .arg(out_dir.join("big.bin.manifest"))
+        .arg(&out_dir)
+        .arg(&rebuilt)
+        .args(["--jobs", "2"])
+        .output()
+        .expect("run dvel-file");
+    assert!(
+        out.status.success(),
+        "download failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(std::fs::read(&rebuilt).unwrap(), data);
+}
+
+#[test]
+fn upload_rejects_zero_jobs() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("f.bin");
+    std::fs::write(&input, b"data").unwrap();
+    for bad in [
+        ["--jobs", "0"],
+        ["--io-buffer", "0"],
+        ["--readahead", "999999999"],
+    ] {
+        let out = dvel_file()
+            .arg("upload")
+            .arg(&input)
+            .arg(dir.path().join("out"))
+            .arg("1024")
+            .args(bad)
+            .output()
+            .expect("run dvel-file");
+        assert!(!out.status.success(), "{:?} accepted", bad);
+        assert!(String::from_utf8_lossy(&out.stderr).contains(bad[0]));
+    }
+}
+
+#[test]
+fn verify_bounds_readahead_by_manifest_chunks() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("f.bin");
+    std::fs::write(&input, vec![7u8; 300_000]).unwrap();
+    let out_dir = dir.path().join("chunks");
+    let out = dvel_file()
+        .arg("upload")
+        .arg(&input)
+        .arg(&out_dir)
+        .arg("300000")
+        .output()
+        .expect("run dvel-file");
+    assert!(out.status.success());
+
+    // 65536 chunks of 300000 bytes would buffer over 16 GiB.
+    for sub in ["verify", "download"] {
+        let mut cmd = dvel_file();
+        cmd.arg(sub)
+            .arg(out_dir.join("f.bin.manifest"))
+            .arg(&out_dir);
+        if sub == "download" {
+            cmd.arg(dir.path().join("rebuilt.bin"));
+        }
+        let out = cmd
+            .args(["--readahead", "65536"])
+            .output()
+            .expect("run dvel-file");
+        assert!(!out.status.success(), "{} accepted", sub);
+        assert!(String::from_utf8_lossy(&out.stderr).contains("--readahead"));
+    }
+}
+
+#[cfg(feature = "keygen")]
+#[test]
+fn keygen_writes_usable_key_pair() {
+    let dir = tempfile::tempdir().unwrap();
+    let prefix = dir.path().join("release");
+    let out = dvel_file()
+        .arg("keygen")
+        .arg("--out")
+        .arg(&prefix)
+        .output()
+        .expect("run dvel-file");
+    assert!(
+        out.status.success(),
+        "keygen failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let secret_path = dir.path().join("release.secret");
+    let public = std::fs::read_to_string(dir.path().join("release.public")).unwrap();
+    let public = public.trim_end();
+    assert_eq!(public.len(), 64);
+    assert!(String::from_utf8_lossy(&out.stdout).contains(&format!("public={}", public)));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&secret_path)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    // The printed public key is what --expect-signer takes.
+    let input = dir.path().join("f.bin");
+    std::fs::write(&input, b"signed payload").unwrap();
+    let chunks = dir.path().join("chunks");
+    let out = dvel_file()
+        .arg("upload")
+        .arg(&input)
+        .arg(&chunks)
+        .arg("4")
+        .arg("--sign-file")
+        .arg(&secret_path)
+        .env_remove("DVEL_SIGN_KEY")
+        .output()
+        .expect("run dvel-file");
+    assert!(out.status.success());
+    let out = dvel_file()
+        .arg("verify")
+        .arg(chunks.join("f.bin.manifest"))
+        .arg(&chunks)
+        .args(["--expect-signer", public])
+        .output()
+        .expect("run dvel-file");
+    assert!(
+        out.status.success(),
+        "verify failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let before = std::fs::read(&secret_path).unwrap();
+    let out = dvel_file()
+        .arg("keygen")
+        .arg("--out")
+        .arg(&prefix)
+        .output()
+        .expect("run dvel-file");
+    assert!(!out.status.success());
+    assert_eq!(std::fs::read(&secret_path).unwrap(), before);
+
+    let out = dvel_file()
+        .arg("keygen")
+        .arg("--out")
+        .arg(&prefix)
+        .arg("--force")
+        .output()
+        .expect("run dvel-file");
+    assert!(out.status.success());
+    assert_ne!(std::fs::read(&secret_path).unwrap(), before);
+}
+
+#[cfg(all(unix, feature = "keygen"))]
+#[test]
+fn keygen_never_writes_through_symlinks() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("victim");
+    std::fs::write(&target, b"keep me").unwrap();
+    let prefix = dir.path().join("k");
+    std::os::unix::fs::symlink(&target, dir.path().join("k.secret")).unwrap();
+
+    let out = dvel_file()
+        .arg("keygen")
+        .arg("--out")
+        .arg(&prefix)
+        .output()
+        .expect("run dvel-file");
+    assert!(!out.status.success());
+    assert!(!dir.path().join("k.public").exists());
+
+    let out = dvel_file()
+        .arg("keygen")
+        .arg("--out")
+        .arg(&prefix)
+        .arg("--force")
+        .output()
+        .expect("run dvel-file");
+    assert!(out.status.success());
+    assert_eq!(std::fs::read(&target).unwrap(), b"keep me");
+    let secret = dir.path().join("k.secret");
+    assert!(!std::fs::symlink_metadata(&secret).unwrap().is_symlink());
+}
+
+#[cfg(not(feature = "keygen"))]
+#[test]
+fn keygen_requires_feature() {
+    let out = dvel_file().arg("keygen").output().expect("run dvel-file");
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--features keygen"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn upload_json_writes_equivalent_manifest() {
+    use dvel_core::storage::{Manifest, read_manifest};
+
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("f.bin");
+    std::fs::write(&input, b"json sidecar payload").unwrap();
+    let chunks = dir.path().join("chunks");
+    let out = dvel_file()
+        .arg("upload")
+        .arg(&input)
+        .arg(&chunks)
+        .arg("8")
+        .arg("--sign")
+        .arg("07".repeat(32))
+        .arg("--json")
+        .env_remove("DVEL_SIGN_KEY")
+        .output()
+        .expect("run dvel-file");
+    assert!(
+        out.status.success(),
+        "upload failed: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let text = read_manifest(&chunks.join("f.bin.manifest")).unwrap();
+    let json = std::fs::read_to_string(chunks.join("f.bin.manifest.json")).unwrap();
+    let from_json = Manifest::from_json(&json).unwrap();
+    assert_eq!(
+        from_json.to_string_with_signature(),
+        text.to_string_with_signature()
+    );
+}
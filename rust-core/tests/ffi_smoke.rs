@@ -1,7 +1,7 @@
 use dvel_core::ffi::*;
 use dvel_core::PROTOCOL_VERSION;
 use std::ffi::CString;
-use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
 use std::ptr;
 use tempfile::tempdir;
 
@@ -25,6 +25,17 @@ fn make_secret(tag: u8) -> dvel_hash_t {
     dvel_hash_t { bytes: h }
 }
 
+#[cfg(unix)]
+fn c_path(path: &Path) -> CString {
+    use std::os::unix::ffi::OsStrExt;
+    CString::new(path.as_os_str().as_bytes()).unwrap()
+}
+
+#[cfg(not(unix))]
+fn c_path(path: &Path) -> CString {
+    CString::new(path.to_str().unwrap()).unwrap()
+}
+
 struct LedgerHandle(*mut dvel_core::ledger::Ledger);
 impl LedgerHandle {
     fn new() -> Self {
@@ -129,6 +140,43 @@ fn ffi_link_rejects_missing_parent() {
     ));
 }
 
+#[test]
+fn ffi_link_policies_report_matching_codes() {
+    use dvel_core::ledger::LinkError;
+    let ev = |prev: dvel_hash_t, tag: u8| dvel_event_t {
+        version: PROTOCOL_VERSION,
+        prev_hash: prev,
+        author: dvel_pubkey_t { bytes: [tag; 32] },
+        timestamp: tag as u64,
+        payload_hash: make_payload(tag),
+        signature: dvel_sig_t { bytes: [0u8; 64] },
+    };
+    let ledger = LedgerHandle::new();
+    dvel_ledger_set_single_genesis(ledger.0, true);
+    dvel_ledger_set_max_tips(ledger.0, 1);
+
+    let mut g = zero_hash();
+    let lr = dvel_ledger_link_event(ledger.0, &ev(zero_hash(), 1), &mut g);
+    assert_eq!(lr, dvel_link_result_t::DVEL_LINK_OK);
+    let mut a = zero_hash();
+    let lr = dvel_ledger_link_event(ledger.0, &ev(g, 2), &mut a);
+    assert_eq!(lr, dvel_link_result_t::DVEL_LINK_OK);
+
+    let mut out = zero_hash();
+    let lr = dvel_ledger_link_event(ledger.0, &ev(zero_hash(), 3), &mut out);
+    assert_eq!(lr, dvel_link_result_t::DVEL_LINK_ERR_INVALID_GENESIS);
+    assert_eq!(lr as u32, LinkError::InvalidGenesis.code());
+
+    let lr = dvel_ledger_link_event(ledger.0, &ev(g, 4), &mut out);
+    assert_eq!(lr, dvel_link_result_t::DVEL_LINK_ERR_TOO_MANY_TIPS);
+    assert_eq!(lr as u32, LinkError::TooManyTips { limit: 1 }.code());
+    assert!(last_error().contains("tip cap"));
+
+    dvel_ledger_set_max_tips(ledger.0, 0);
+    let lr = dvel_ledger_link_event(ledger.0, &ev(g, 4), &mut out);
+    assert_eq!(lr, dvel_link_result_t::DVEL_LINK_OK);
+}
+
 #[test]
 fn ffi_storage_round_trip() {
     let dir = tempdir().unwrap();
@@ -136,8 +184,8 @@ fn ffi_storage_round_trip() {
     std::fs::write(&input_path, b"ffi storage round trip").unwrap();
 
     let out_dir = dir.path().join("chunks");
-    let c_input = CString::new(input_path.as_os_str().as_bytes()).unwrap();
-    let c_out = CString::new(out_dir.as_os_str().as_bytes()).unwrap();
+    let c_input = c_path(&input_path);
+    let c_out = c_path(&out_dir);
 
     let ok = dvel_storage_chunk_file(c_input.as_ptr(), c_out.as_ptr(), 8, ptr::null(), false);
     assert!(ok, "chunk_file failed");
@@ -145,9 +193,9 @@ fn ffi_storage_round_trip() {
     let manifest_path = out_dir.join("input.bin.manifest");
     let rebuilt_path = dir.path().join("rebuilt.bin");
 
-    let c_manifest = CString::new(manifest_path.as_os_str().as_bytes()).unwrap();
-    let c_chunk_dir = CString::new(out_dir.as_os_str().as_bytes()).unwrap();
-    let c_rebuilt = CString::new(rebuilt_path.as_os_str().as_bytes()).unwrap();
+    let c_manifest = c_path(&manifest_path);
+    let c_chunk_dir = c_path(&out_dir);
+    let c_rebuilt = c_path(&rebuilt_path);
 
     let ok = dvel_storage_download(
         c_manifest.as_ptr(),
@@ -175,3 +223,226 @@ fn ffi_storage_round_trip() {
     assert!(manifest_hash.bytes.iter().any(|b| *b != 0));
     assert!(chunk_root.bytes.iter().any(|b| *b != 0));
 }
+
+fn last_error() -> String {
+    let needed = dvel_last_error_message(ptr::null_mut(), 0);
+    let mut buf = vec![0u8; needed];
+    assert_eq!(
+        dvel_last_error_message(buf.as_mut_ptr().cast(), buf.len()),
+        needed
+    );
+    assert_eq!(buf[needed - 1], 0);
+    String::from_utf8(buf[..needed - 1].to_vec()).unwrap()
+}
+
+#[test]
+fn ffi_last_error_reports_hash_mismatch() {
+    let dir = tempdir().unwrap();
+    let input_path = dir.path().join("input.bin");
+    std::fs::write(&input_path, b"ffi last error message").unwrap();
+    let out_dir = dir.path().join("chunks");
+    let c_input = c_path(&input_path);
+    let c_out = c_path(&out_dir);
+    assert!(dvel_storage_chunk_file(
+        c_input.as_ptr(),
+        c_out.as_ptr(),
+        8,
+        ptr::null(),
+        false
+    ));
+
+    let chunk = out_dir.join("input.bin.chunk.00000001");
+    let mut bytes = std::fs::read(&chunk).unwrap();
+    bytes[0] ^= 0xff;
+    std::fs::write(&chunk, bytes).unwrap();
+
+    let c_manifest = c_path(&out_dir.join("input.bin.manifest"));
+    let c_rebuilt = c_path(&dir.path().join("rebuilt.bin"));
+    assert!(!dvel_storage_download(
+        c_manifest.as_ptr(),
+        c_out.as_ptr(),
+        c_rebuilt.as_ptr(),
+        ptr::null(),
+    ));
+    let msg = last_error();
+    assert!(msg.contains("hash mismatch"), "{}", msg);
+
+    // Truncated copies stay NUL-terminated.
+    let mut small = [0xffu8; 6];
+    assert_eq!(
+        dvel_last_error_message(small.as_mut_ptr().cast(), small.len()),
+        msg.len() + 1
+    );
+    assert_eq!(&small[..5], &msg.as_bytes()[..5]);
+    assert_eq!(small[5], 0);
+
+    // Ledger link failures map their result enum to a message too.
+    let ledger = LedgerHandle::new();
+    let ev = dvel_event_t {
+        version: PROTOCOL_VERSION,
+        prev_hash: make_payload(9),
+        author: dvel_pubkey_t { bytes: [1u8; 32] },
+        timestamp: 1,
+        payload_hash: make_payload(1),
+        signature: dvel_sig_t { bytes: [0u8; 64] },
+    };
+    let mut out_hash = zero_hash();
+    dvel_ledger_link_event(ledger.0, &ev, &mut out_hash);
+    assert_eq!(last_error(), "parent event not in ledger");
+}
+
+#[test]
+fn ffi_sybil_overlay_scores_author() {
+    let secret = make_secret(11);
+    let mut author = dvel_pubkey_t { bytes: [0u8; 32] };
+    assert!(dvel_derive_pubkey_from_secret(&secret, &mut author));
+
+    let mut ev = dvel_event_t {
+        version: PROTOCOL_VERSION,
+        prev_hash: zero_hash(),
+        author,
+        timestamp: 10,
+        payload_hash: make_payload(0xCD),
+        signature: dvel_sig_t { bytes: [0u8; 64] },
+    };
+    dvel_sign_event(&ev, &secret, &mut ev.signature);
+
+    let ledger = LedgerHandle::new();
+    let mut out_hash = zero_hash();
+    let lr = dvel_ledger_link_event(ledger.0, &ev, &mut out_hash);
+    assert!(matches!(lr, dvel_link_result_t::DVEL_LINK_OK));
+
+    let cfg = dvel_sybil_config_t {
+        warmup_ticks: 4,
+        quarantine_ticks: 8,
+        fixed_point_scale: 1000,
+        max_link_walk: 64,
+    };
+    let overlay = dvel_sybil_new(&cfg);
+    assert!(!overlay.is_null());
+    assert_eq!(dvel_sybil_author_weight(overlay, author, 10), 0);
+
+    assert!(dvel_sybil_observe_event(
+        overlay,
+        ledger.0,
+        10,
+        0,
+        &ev,
+        ptr::null()
+    ));
+    let weight = dvel_sybil_author_weight(overlay, author, 20);
+    assert!(weight > 0 && weight <= cfg.fixed_point_scale, "{}", weight);
+    assert_eq!(
+        weight,
+        dvel_sybil_overlay_author_weight_fp(overlay, 20, author)
+    );
+
+    assert!(!dvel_sybil_observe_event(
+        overlay,
+        ptr::null(),
+        10,
+        0,
+        &ev,
+        &out_hash
+    ));
+    assert_eq!(last_error(), "ledger is null");
+
+    // Policies arrive as plain integers; unknown values and null handles are rejected.
+    assert!(dvel_sybil_set_policy(
+        overlay,
+        dvel_equivocation_policy_t::DVEL_POLICY_SLASH as u32
+    ));
+    assert!(!dvel_sybil_set_policy(overlay, 2));
+    assert_eq!(last_error(), "unknown equivocation policy 2");
+    assert!(!dvel_sybil_set_policy(
+        ptr::null_mut(),
+        dvel_equivocation_policy_t::DVEL_POLICY_QUARANTINE as u32
+    ));
+    dvel_sybil_free(overlay);
+}
+
+#[cfg(windows)]
+fn w_path(path: &Path) -> Vec<u16> {
+    use std::os::windows::ffi::OsStrExt;
+    path.as_os_str().encode_wide().chain(Some(0)).collect()
+}
+
+#[test]
+fn ffi_storage_non_ascii_path_round_trip() {
+    let dir = tempdir().unwrap();
+    let base = dir.path().join("répertoire-ü");
+    std::fs::create_dir(&base).unwrap();
+    let input_path = base.join("données-日本.bin");
+    let data = b"non-ascii path round trip".to_vec();
+    std::fs::write(&input_path, &data).unwrap();
+
+    let out_dir = base.join("chunks-é");
+    let manifest_path = out_dir.join("données-日本.bin.manifest");
+    let rebuilt_path = base.join("rebuilt-ß.bin");
+
+    #[cfg(not(windows))]
+    {
+        assert!(dvel_storage_chunk_file(
+            c_path(&input_path).as_ptr(),
+            c_path(&out_dir).as_ptr(),
+            8,
+            ptr::null(),
+            false
+        ));
+        assert!(dvel_storage_download(
+            c_path(&manifest_path).as_ptr(),
+            c_path(&out_dir).as_ptr(),
+            c_path(&rebuilt_path).as_ptr(),
+            ptr::null(),
+        ));
+    }
+    #[cfg(windows)]
+    {
+        assert!(dvel_storage_chunk_file_w(
+            w_path(&input_path).as_ptr(),
+            w_path(&out_dir).as_ptr(),
+            8,
+            ptr::null(),
+            false
+        ));
+        assert!(dvel_storage_download_w(
+            w_path(&manifest_path).as_ptr(),
+            w_path(&out_dir).as_ptr(),
+            w_path(&rebuilt_path).as_ptr(),
+            ptr::null(),
+        ));
+    }
+    assert_eq!(std::fs::read(&rebuilt_path).unwrap(), data);
+}
+
+// Byte-path entry points take any Unix path bytes; only the file name itself must be UTF-8,
+// since it is recorded in the manifest.
+#[cfg(target_os = "linux")]
+#[test]
+fn ffi_storage_non_utf8_directory() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = tempdir().unwrap();
+    let base = dir.path().join(OsStr::from_bytes(b"raw-\xff-dir"));
+    std::fs::create_dir(&base).unwrap();
+    let input_path = base.join("input.bin");
+    std::fs::write(&input_path, b"non-utf8 directory").unwrap();
+    let out_dir = base.join("chunks");
+
+    assert!(dvel_storage_chunk_file(
+        c_path(&input_path).as_ptr(),
+        c_path(&out_dir).as_ptr(),
+        8,
+        ptr::null(),
+        false
+    ));
+    let rebuilt_path = base.join("rebuilt.bin");
+    assert!(dvel_storage_download(
+        c_path(&out_dir.join("input.bin.manifest")).as_ptr(),
+        c_path(&out_dir).as_ptr(),
+        c_path(&rebuilt_path).as_ptr(),
+        ptr::null(),
+    ));
+    assert_eq!(std::fs::read(&rebuilt_path).unwrap(), b"non-utf8 directory");
+}